@@ -0,0 +1,428 @@
+//! Statistics over a batch of replays, i.e. a ladder session or a team's practice games.
+//! The statistics are computed per player game, optionally restricted to the games of one
+//! battle.net account.
+
+use crate::{GameResult, ProcessedReplay, ReplayPlayer, Toon};
+use std::collections::BTreeMap;
+
+/// The in-game time the worker count is compared at.
+pub const WORKERS_AT_SECONDS: f32 = 360.;
+
+/// The worker count usually considered as fully saturating three bases.
+pub const SATURATION_WORKERS: i32 = 66;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct WinRecord {
+    pub wins: usize,
+    pub losses: usize,
+    /// Ties and undecided games.
+    pub others: usize,
+}
+
+impl WinRecord {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.others
+    }
+
+    /// The wins over all the games, between 0 and 1.
+    pub fn win_rate(&self) -> f32 {
+        if self.games() == 0 {
+            0.
+        } else {
+            self.wins as f32 / self.games() as f32
+        }
+    }
+
+    fn add(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Loss => self.losses += 1,
+            GameResult::Tie | GameResult::Undecided => self.others += 1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AggregateStats {
+    /// The number of replays the player games were taken from.
+    pub replays: usize,
+    /// The number of player games considered.
+    pub player_games: usize,
+    pub by_race: BTreeMap<String, WinRecord>,
+    /// Keyed by the races initials, the considered player's team first, i.e. `PvZ`.
+    pub by_matchup: BTreeMap<String, WinRecord>,
+    pub by_map: BTreeMap<String, WinRecord>,
+    pub average_game_seconds: Option<f32>,
+    /// The average worker count at `WORKERS_AT_SECONDS`, games shorter than that are skipped.
+    pub average_workers_at_six: Option<f32>,
+    /// The median in-game time to `SATURATION_WORKERS` workers, of the games that reached it.
+    pub median_seconds_to_saturation: Option<f32>,
+}
+
+impl AggregateStats {
+    /// Computes the statistics of every player of the `replays`, or only of the players with the
+    /// `toon` account.
+    pub fn new<'a>(
+        replays: impl IntoIterator<Item = &'a ProcessedReplay>,
+        toon: Option<Toon>,
+    ) -> Self {
+        let mut stats = Self::default();
+        let mut game_seconds = vec![];
+        let mut workers_at_six = vec![];
+        let mut seconds_to_saturation = vec![];
+        for replay in replays {
+            let players: Vec<(u8, &ReplayPlayer)> = replay
+                .players()
+                .filter(|(_, player)| toon.map_or(true, |toon| player.toon == toon))
+                .collect();
+            if players.is_empty() {
+                continue;
+            }
+            stats.replays += 1;
+            game_seconds.push(replay.game_speed.loops_to_seconds(replay.last_frame));
+            for (player_id, player) in players {
+                stats.player_games += 1;
+                stats
+                    .by_race
+                    .entry(player.race.clone())
+                    .or_default()
+                    .add(player.result);
+                stats
+                    .by_matchup
+                    .entry(matchup(replay, player))
+                    .or_default()
+                    .add(player.result);
+                stats
+                    .by_map
+                    .entry(replay.details.map_name().to_string())
+                    .or_default()
+                    .add(player.result);
+                if let Some(workers) = workers_at(replay, player_id, WORKERS_AT_SECONDS) {
+                    workers_at_six.push(workers as f32);
+                }
+                if let Some(seconds) = seconds_to_workers(replay, player_id, SATURATION_WORKERS) {
+                    seconds_to_saturation.push(seconds);
+                }
+            }
+        }
+        stats.average_game_seconds = average(&game_seconds);
+        stats.average_workers_at_six = average(&workers_at_six);
+        stats.median_seconds_to_saturation = median(seconds_to_saturation);
+        stats
+    }
+}
+
+/// The accounts that played in the `replays`, with the last name they used, to filter on.
+pub fn toons<'a>(replays: impl IntoIterator<Item = &'a ProcessedReplay>) -> BTreeMap<Toon, String> {
+    let mut toons = BTreeMap::new();
+    for replay in replays {
+        for (player_id, player) in replay.players() {
+            toons.insert(player.toon, replay.roster.name_by_player_id(player_id));
+        }
+    }
+    toons
+}
+
+/// The matchup from the point of view of `player`, i.e. `PvZ` or `PTvZZ` in team games.
+fn matchup(replay: &ProcessedReplay, player: &ReplayPlayer) -> String {
+    let mut own = vec![];
+    let mut others = vec![];
+    for (_, other) in replay.players() {
+        let initial = other.race.chars().next().unwrap_or('?');
+        if other.team_id == player.team_id {
+            own.push(initial);
+        } else {
+            others.push(initial);
+        }
+    }
+    own.sort_unstable();
+    others.sort_unstable();
+    format!(
+        "{}v{}",
+        own.into_iter().collect::<String>(),
+        others.into_iter().collect::<String>()
+    )
+}
+
+/// The worker count of the last snapshot at or before `seconds`, None if the game ended before.
+fn workers_at(replay: &ProcessedReplay, player_id: u8, seconds: f32) -> Option<i32> {
+    let frame = replay.game_speed.seconds_to_loops(seconds);
+    if replay.last_frame < frame {
+        return None;
+    }
+    replay
+        .game_snapshots
+        .iter()
+        .filter(|snapshot| snapshot.player_id == player_id && snapshot.frame <= frame)
+        .last()
+        .map(|snapshot| snapshot.supply_workers)
+}
+
+/// The in-game seconds of the first snapshot with at least `workers` workers.
+fn seconds_to_workers(replay: &ProcessedReplay, player_id: u8, workers: i32) -> Option<f32> {
+    replay
+        .game_snapshots
+        .iter()
+        .find(|snapshot| snapshot.player_id == player_id && snapshot.supply_workers >= workers)
+        .map(|snapshot| replay.game_speed.loops_to_seconds(snapshot.frame))
+}
+
+fn average(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+fn median(mut values: Vec<f32>) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        Some((values[mid - 1] + values[mid]) / 2.)
+    } else {
+        Some(values[mid])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::{SummaryEvents, SummaryMetadata, SummaryPlayer, SummaryTimeSeries};
+    use crate::{GameSnapshot, GameSpeed, PlayerColor, ReplayDate, ReplaySummary};
+
+    fn toon(id: u64) -> Toon {
+        Toon {
+            region: 2,
+            realm: 1,
+            id,
+        }
+    }
+
+    fn player(
+        player_id: u8,
+        race: &str,
+        team_id: u8,
+        result: GameResult,
+        toon_id: u64,
+    ) -> SummaryPlayer {
+        SummaryPlayer {
+            player_id,
+            user_id: Some(player_id as u32 - 1),
+            slot_id: Some(player_id - 1),
+            is_ai: false,
+            details: ReplayPlayer {
+                name: format!("player{}", player_id),
+                race: race.to_string(),
+                result,
+                team_id,
+                color: PlayerColor {
+                    r: 0,
+                    g: 0,
+                    b: 0,
+                    a: 255,
+                },
+                toon: toon(toon_id),
+            },
+        }
+    }
+
+    fn snapshot(frame: u32, player_id: u8, supply_workers: i32) -> GameSnapshot {
+        serde_json::from_value(serde_json::json!({
+            "frame": frame,
+            "player_id": player_id,
+            "minerals": 0,
+            "vespene": 0,
+            "mineral_income": 0,
+            "vespene_income": 0,
+            "supply_available": 200,
+            "supply_used": supply_workers,
+            "supply_workers": supply_workers,
+            "active_force_minerals": 0,
+            "active_force_vespene": 0,
+        }))
+        .unwrap()
+    }
+
+    /// Played at Normal speed, 16 loops per second, so that the frames convert to round seconds.
+    fn replay(
+        last_frame: u32,
+        roster: Vec<SummaryPlayer>,
+        game_snapshots: Vec<GameSnapshot>,
+    ) -> ProcessedReplay {
+        ProcessedReplay::from_summary(ReplaySummary {
+            schema_version: crate::SUMMARY_SCHEMA_VERSION,
+            metadata: SummaryMetadata {
+                name: "game.SC2Replay".to_string(),
+                title: "Altitude LE".to_string(),
+                map_file_name: String::new(),
+                description: String::new(),
+                is_blizzard_map: true,
+                game_speed: GameSpeed::Normal,
+                date: ReplayDate {
+                    unix_seconds: 1_672_531_200,
+                    local_offset_seconds: 0,
+                },
+                last_frame,
+                duration_seconds: last_frame / 16,
+                content_hash: None,
+                random_seed: None,
+                map_size: None,
+            },
+            roster,
+            observers: vec![],
+            time_series: SummaryTimeSeries {
+                game_snapshots,
+                apm: vec![],
+            },
+            events: SummaryEvents {
+                messages: vec![],
+                units: vec![],
+                upgrades: vec![],
+                build_order: vec![],
+            },
+        })
+    }
+
+    fn one_v_one(last_frame: u32, game_snapshots: Vec<GameSnapshot>) -> ProcessedReplay {
+        replay(
+            last_frame,
+            vec![
+                player(1, "Protoss", 0, GameResult::Win, 1),
+                player(2, "Zerg", 1, GameResult::Loss, 2),
+            ],
+            game_snapshots,
+        )
+    }
+
+    #[test]
+    fn win_rate() {
+        let record = WinRecord {
+            wins: 3,
+            losses: 0,
+            others: 1,
+        };
+        assert_eq!(record.games(), 4);
+        assert_eq!(record.win_rate(), 0.75);
+        assert_eq!(WinRecord::default().win_rate(), 0.);
+    }
+
+    #[test]
+    fn matchup_puts_the_player_team_first() {
+        let replay = one_v_one(9600, vec![]);
+        let players: Vec<&ReplayPlayer> = replay.players().map(|(_, player)| player).collect();
+        assert_eq!(matchup(&replay, players[0]), "PvZ");
+        assert_eq!(matchup(&replay, players[1]), "ZvP");
+    }
+
+    #[test]
+    fn team_matchup_races_are_sorted() {
+        let replay = replay(
+            9600,
+            vec![
+                player(1, "Terran", 0, GameResult::Win, 1),
+                player(2, "Zerg", 1, GameResult::Loss, 2),
+                player(3, "Protoss", 0, GameResult::Win, 3),
+                player(4, "Zerg", 1, GameResult::Loss, 4),
+            ],
+            vec![],
+        );
+        let players: Vec<&ReplayPlayer> = replay.players().map(|(_, player)| player).collect();
+        assert_eq!(matchup(&replay, players[0]), "PTvZZ");
+        assert_eq!(matchup(&replay, players[3]), "ZZvPT");
+    }
+
+    #[test]
+    fn workers_at_takes_the_last_snapshot_before() {
+        let replay = one_v_one(
+            9600,
+            vec![
+                snapshot(5440, 1, 40),
+                snapshot(5440, 2, 35),
+                snapshot(5760, 1, 44),
+                snapshot(6080, 1, 48),
+            ],
+        );
+        assert_eq!(workers_at(&replay, 1, WORKERS_AT_SECONDS), Some(44));
+        assert_eq!(workers_at(&replay, 2, WORKERS_AT_SECONDS), Some(35));
+    }
+
+    #[test]
+    fn workers_at_is_none_for_games_shorter_than_six_minutes() {
+        let replay = one_v_one(5600, vec![snapshot(5440, 1, 40)]);
+        assert_eq!(workers_at(&replay, 1, WORKERS_AT_SECONDS), None);
+    }
+
+    #[test]
+    fn seconds_to_workers_finds_the_first_snapshot_reaching_the_count() {
+        let replay = one_v_one(
+            12800,
+            vec![
+                snapshot(4800, 1, 40),
+                snapshot(4800, 2, 66),
+                snapshot(9600, 1, 66),
+                snapshot(11200, 1, 70),
+            ],
+        );
+        assert_eq!(
+            seconds_to_workers(&replay, 1, SATURATION_WORKERS),
+            Some(600.)
+        );
+        assert_eq!(
+            seconds_to_workers(&replay, 2, SATURATION_WORKERS),
+            Some(300.)
+        );
+        assert_eq!(seconds_to_workers(&replay, 1, 80), None);
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![3., 1., 2.]), Some(2.));
+        assert_eq!(median(vec![4., 1., 3., 2.]), Some(2.5));
+    }
+
+    #[test]
+    fn toon_filter_keeps_the_games_of_the_account() {
+        let replays = vec![
+            one_v_one(9600, vec![]),
+            replay(
+                9600,
+                vec![
+                    player(1, "Terran", 0, GameResult::Loss, 3),
+                    player(2, "Zerg", 1, GameResult::Win, 2),
+                ],
+                vec![],
+            ),
+            replay(
+                9600,
+                vec![
+                    player(1, "Terran", 0, GameResult::Win, 3),
+                    player(2, "Terran", 1, GameResult::Loss, 4),
+                ],
+                vec![],
+            ),
+        ];
+
+        let all = AggregateStats::new(&replays, None);
+        assert_eq!(all.replays, 3);
+        assert_eq!(all.player_games, 6);
+
+        let stats = AggregateStats::new(&replays, Some(toon(2)));
+        assert_eq!(stats.replays, 2);
+        assert_eq!(stats.player_games, 2);
+        assert_eq!(
+            stats.by_race.get("Zerg"),
+            Some(&WinRecord {
+                wins: 1,
+                losses: 1,
+                others: 0,
+            })
+        );
+        assert_eq!(stats.by_race.len(), 1);
+        assert_eq!(stats.by_matchup.keys().collect::<Vec<_>>(), ["ZvP", "ZvT"]);
+    }
+}
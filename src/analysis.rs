@@ -0,0 +1,430 @@
+//! UI independent analysis of SC2Replay files.
+//! Everything in this module compiles without yew/web-sys so that it can be used from native
+//! tools, tests or servers as well as from the web `App`.
+
+use crate::engagements::{find_engagements, unit_deaths, Engagement, UnitDeath};
+use crate::identity::sorted_toons;
+use crate::lobby::ReplayLobby;
+use crate::summary::is_summary_file_name;
+use crate::{
+    compute_apm, extract_build_order, extract_chat_messages, extract_upgrades, BuildOrderEntry,
+    ChatMessage, GameSpeed, PlayerApm, PlayerRoster, ReplayDate, ReplayDetails, ReplayError,
+    ReplayIdentity, ReplayPlayer, ReplaySummary, RosterEntry, UnitRegistry, UpgradeCompleted,
+};
+use nom_mpq::parser;
+use s2protocol::tracker_events::ReplayTrackerEvent::PlayerStats;
+use s2protocol::tracker_events::TrackerEvent;
+use s2protocol::versions::{
+    read_details, read_game_events, read_init_data, read_message_events, read_tracker_events,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The protocol base builds s2protocol can decode, the `read_*` functions panic on others so the
+/// version is checked before calling them.
+pub const SUPPORTED_BASE_BUILDS: [u32; 8] =
+    [87702, 88500, 89165, 89634, 89720, 90136, 90779, 90870];
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub frame: u32,
+    /// The tracker events player id.
+    pub player_id: u8,
+    pub minerals: i32,
+    pub vespene: i32,
+    pub mineral_income: i32,
+    pub vespene_income: i32,
+    /// The supply provided by the structures and units, it can exceed the 200 cap.
+    pub supply_available: i32,
+    pub supply_used: i32,
+    pub supply_workers: i32,
+    pub active_force_minerals: i32,
+    pub active_force_vespene: i32,
+    // The fields below are missing from summaries written before they were captured.
+    // The resources of the units and structures being built and of those completed, by category.
+    #[serde(default)]
+    pub minerals_used_in_progress_army: i32,
+    #[serde(default)]
+    pub minerals_used_in_progress_economy: i32,
+    #[serde(default)]
+    pub minerals_used_in_progress_technology: i32,
+    #[serde(default)]
+    pub vespene_used_in_progress_army: i32,
+    #[serde(default)]
+    pub vespene_used_in_progress_economy: i32,
+    #[serde(default)]
+    pub vespene_used_in_progress_technology: i32,
+    #[serde(default)]
+    pub minerals_used_current_army: i32,
+    #[serde(default)]
+    pub minerals_used_current_economy: i32,
+    #[serde(default)]
+    pub minerals_used_current_technology: i32,
+    #[serde(default)]
+    pub vespene_used_current_army: i32,
+    #[serde(default)]
+    pub vespene_used_current_economy: i32,
+    #[serde(default)]
+    pub vespene_used_current_technology: i32,
+    // The damage dealt to the player's own units and structures, in resources.
+    #[serde(default)]
+    pub minerals_friendly_fire_army: i32,
+    #[serde(default)]
+    pub minerals_friendly_fire_economy: i32,
+    #[serde(default)]
+    pub minerals_friendly_fire_technology: i32,
+    #[serde(default)]
+    pub vespene_friendly_fire_army: i32,
+    #[serde(default)]
+    pub vespene_friendly_fire_economy: i32,
+    #[serde(default)]
+    pub vespene_friendly_fire_technology: i32,
+    // The resources of the units and structures the player lost, and of those of the opponents
+    // the player killed, by category.
+    #[serde(default)]
+    pub minerals_lost_army: i32,
+    #[serde(default)]
+    pub minerals_lost_economy: i32,
+    #[serde(default)]
+    pub minerals_lost_technology: i32,
+    #[serde(default)]
+    pub vespene_lost_army: i32,
+    #[serde(default)]
+    pub vespene_lost_economy: i32,
+    #[serde(default)]
+    pub vespene_lost_technology: i32,
+    #[serde(default)]
+    pub minerals_killed_army: i32,
+    #[serde(default)]
+    pub minerals_killed_economy: i32,
+    #[serde(default)]
+    pub minerals_killed_technology: i32,
+    #[serde(default)]
+    pub vespene_killed_army: i32,
+    #[serde(default)]
+    pub vespene_killed_economy: i32,
+    #[serde(default)]
+    pub vespene_killed_technology: i32,
+}
+
+impl GameSnapshot {
+    /// The minerals and vespene lost so far, army, economy and technology.
+    pub fn resources_lost(&self) -> i32 {
+        self.minerals_lost_army
+            + self.minerals_lost_economy
+            + self.minerals_lost_technology
+            + self.vespene_lost_army
+            + self.vespene_lost_economy
+            + self.vespene_lost_technology
+    }
+
+    /// The minerals and vespene of the opponents units and structures killed so far.
+    pub fn resources_killed(&self) -> i32 {
+        self.minerals_killed_army
+            + self.minerals_killed_economy
+            + self.minerals_killed_technology
+            + self.vespene_killed_army
+            + self.vespene_killed_economy
+            + self.vespene_killed_technology
+    }
+}
+
+pub struct ProcessedReplay {
+    pub name: String,
+    /// Tells whether two replays are the same file or the same game.
+    pub identity: ReplayIdentity,
+    pub details: ReplayDetails,
+    pub roster: PlayerRoster,
+    pub game_speed: GameSpeed,
+    pub date: ReplayDate,
+    /// The last game loop of the tracker events, the length of the game.
+    pub last_frame: u32,
+    /// The width and height of the map in cells, unknown for the summaries written before it was
+    /// captured.
+    pub map_size: Option<(u32, u32)>,
+    pub messages: Vec<ChatMessage>,
+    pub game_snapshots: Vec<GameSnapshot>,
+    pub units: UnitRegistry,
+    pub upgrades: Vec<UpgradeCompleted>,
+    pub build_order: Vec<BuildOrderEntry>,
+    /// Derived from `units`, ordered by frame.
+    pub deaths: Vec<UnitDeath>,
+    pub engagements: Vec<Engagement>,
+    /// The APM of every user that performed actions, observers included.
+    pub apm: Vec<PlayerApm>,
+}
+
+impl ProcessedReplay {
+    /// Parses the MPQ archive contained in `data` and extracts the details, messages and
+    /// snapshots of the game.
+    pub fn from_bytes(name: &str, data: &[u8]) -> Result<Self, ReplayError> {
+        let mpq = match parser::parse(data) {
+            Ok((_, mpq)) => mpq,
+            Err(err) => return Err(ReplayError::MpqParse(err.to_string())),
+        };
+        let base_build = match s2protocol::read_protocol_header(&mpq) {
+            Ok((_, proto_header)) => proto_header.m_version.m_base_build,
+            Err(err) => return Err(ReplayError::ProtocolHeader(err.to_string())),
+        };
+        if !SUPPORTED_BASE_BUILDS.contains(&base_build) {
+            return Err(ReplayError::UnsupportedProtocol(base_build));
+        }
+        let details = read_details(&mpq, data);
+        if details.player_list.is_empty() {
+            return Err(ReplayError::MissingDetails);
+        }
+        let lobby = ReplayLobby::from(&read_init_data(&mpq, data));
+        let messages = extract_chat_messages(&read_message_events(&mpq, data));
+        let tracker_events = read_tracker_events(&mpq, data);
+        if tracker_events.is_empty() {
+            return Err(ReplayError::EmptyTrackerEvents);
+        }
+        let game_events = read_game_events(&mpq, data);
+        let last_frame: u32 = tracker_events.iter().map(|event| event.delta).sum();
+        let game_snapshots = extract_game_snapshots(&tracker_events);
+        let units = UnitRegistry::from_tracker_events(&tracker_events);
+        let upgrades = extract_upgrades(&tracker_events);
+        let build_order = extract_build_order(&units, &upgrades, &game_snapshots);
+        let game_speed = GameSpeed::from(&details.game_speed);
+        let deaths = unit_deaths(&units);
+        let engagements = find_engagements(&deaths, game_speed);
+        let apm = compute_apm(&game_events, game_speed, last_frame);
+        let date = ReplayDate::from_filetime(details.time_utc, details.time_local_offset);
+        let replay_details = ReplayDetails::from(&details);
+        Ok(Self {
+            name: name.to_string(),
+            identity: ReplayIdentity::new(
+                data,
+                &replay_details,
+                &date,
+                game_speed.loops_to_seconds(last_frame) as u32,
+                Some(lobby.random_seed),
+            ),
+            roster: PlayerRoster::new(&details, &lobby, &tracker_events),
+            game_speed,
+            date,
+            last_frame,
+            map_size: Some((lobby.map_size_x, lobby.map_size_y)),
+            details: replay_details,
+            messages,
+            game_snapshots,
+            units,
+            upgrades,
+            build_order,
+            deaths,
+            engagements,
+            apm,
+        })
+    }
+
+    /// Processes either an SC2Replay or, for `.json` files, a summary previously exported by
+    /// cooper.
+    pub fn from_file(name: &str, data: &[u8]) -> Result<Self, ReplayError> {
+        if is_summary_file_name(name) {
+            Ok(Self::from_summary(ReplaySummary::from_json(data)?))
+        } else {
+            Self::from_bytes(name, data)
+        }
+    }
+
+    /// Rebuilds a replay from its summary, without the original SC2Replay file.
+    pub fn from_summary(summary: ReplaySummary) -> Self {
+        let mut player_list = vec![];
+        let mut roster = vec![];
+        for player in summary.roster {
+            roster.push(RosterEntry {
+                player_id: player.player_id,
+                user_id: player.user_id,
+                slot_id: player.slot_id,
+                team_id: player.details.team_id,
+                name: minor_player_clan_unescape(&player.details.name),
+                is_ai: player.is_ai,
+                details_idx: player_list.len(),
+            });
+            player_list.push(player.details);
+        }
+        let metadata = summary.metadata;
+        let units = UnitRegistry::from_units(summary.events.units);
+        let deaths = unit_deaths(&units);
+        let engagements = find_engagements(&deaths, metadata.game_speed);
+        let details = ReplayDetails {
+            title: metadata.title,
+            map_file_name: metadata.map_file_name,
+            description: metadata.description,
+            is_blizzard_map: metadata.is_blizzard_map,
+            player_list,
+        };
+        Self {
+            name: metadata.name,
+            identity: ReplayIdentity {
+                content_hash: metadata.content_hash,
+                random_seed: metadata.random_seed,
+                toons: sorted_toons(&details),
+                start_seconds: metadata.date.unix_seconds - i64::from(metadata.duration_seconds),
+            },
+            details,
+            roster: PlayerRoster::from_entries(roster, summary.observers),
+            game_speed: metadata.game_speed,
+            date: metadata.date,
+            last_frame: metadata.last_frame,
+            map_size: metadata.map_size,
+            messages: summary.events.messages,
+            game_snapshots: summary.time_series.game_snapshots,
+            units,
+            upgrades: summary.events.upgrades,
+            build_order: summary.events.build_order,
+            deaths,
+            engagements,
+            apm: summary.time_series.apm,
+        }
+    }
+
+    /// Formats a game loop as the in-game clock at the speed the replay was played.
+    pub fn format_frame(&self, frame: u32) -> String {
+        self.game_speed.format_loops(frame)
+    }
+
+    /// The in-game length of the game, in seconds.
+    pub fn duration_seconds(&self) -> u32 {
+        self.game_speed.loops_to_seconds(self.last_frame) as u32
+    }
+
+    /// The players along with their tracker events player id, in the order of the player list.
+    pub fn players(&self) -> impl Iterator<Item = (u8, &ReplayPlayer)> {
+        self.roster.iter().map(|entry| {
+            (
+                entry.player_id,
+                &self.details.player_list[entry.details_idx],
+            )
+        })
+    }
+
+    /// The players grouped by their team id, along with their tracker events player id.
+    pub fn teams(&self) -> BTreeMap<u8, Vec<(u8, &ReplayPlayer)>> {
+        self.roster
+            .teams()
+            .into_iter()
+            .map(|(team_id, entries)| {
+                let players = entries
+                    .into_iter()
+                    .map(|entry| {
+                        (
+                            entry.player_id,
+                            &self.details.player_list[entry.details_idx],
+                        )
+                    })
+                    .collect();
+                (team_id, players)
+            })
+            .collect()
+    }
+
+    /// The APM of a player, by tracker events player id.
+    pub fn player_apm(&self, player_id: u8) -> Option<&PlayerApm> {
+        let user_id = self.roster.by_player_id(player_id)?.user_id?;
+        self.apm.iter().find(|apm| apm.user_id == user_id)
+    }
+
+    /// The build order of a player, by tracker events player id.
+    pub fn player_build_order(&self, player_id: u8) -> impl Iterator<Item = &BuildOrderEntry> {
+        self.build_order
+            .iter()
+            .filter(move |entry| entry.player_id == player_id)
+    }
+}
+
+/// Do minor HTML enescapes for clan names, Unscaping everything would probably result in
+/// <script>'s etc, or would it? Maybe it's not innerHTML right?
+pub fn minor_player_clan_unescape(input: &str) -> String {
+    input
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("<sp/>", " ")
+}
+
+pub fn extract_game_snapshots(tracker_events: &[TrackerEvent]) -> Vec<GameSnapshot> {
+    let mut frame = 0;
+    let mut snapshots = vec![];
+    for event in tracker_events {
+        frame += event.delta;
+        match &event.event {
+            PlayerStats(player_stats_event) => {
+                snapshots.push(GameSnapshot {
+                    frame,
+                    player_id: player_stats_event.player_id,
+                    minerals: player_stats_event.stats.minerals_current,
+                    vespene: player_stats_event.stats.vespene_current,
+                    mineral_income: player_stats_event.stats.minerals_collection_rate,
+                    vespene_income: player_stats_event.stats.vespene_collection_rate,
+                    supply_available: player_stats_event.stats.food_made,
+                    supply_used: player_stats_event.stats.food_used,
+                    supply_workers: player_stats_event.stats.workers_active_count,
+                    active_force_minerals: player_stats_event.stats.minerals_used_active_forces,
+                    active_force_vespene: player_stats_event.stats.vespene_used_active_forces,
+                    minerals_used_in_progress_army: player_stats_event
+                        .stats
+                        .minerals_used_in_progress_army,
+                    minerals_used_in_progress_economy: player_stats_event
+                        .stats
+                        .minerals_used_in_progress_economy,
+                    minerals_used_in_progress_technology: player_stats_event
+                        .stats
+                        .minerals_used_in_progress_technology,
+                    vespene_used_in_progress_army: player_stats_event
+                        .stats
+                        .vespene_used_in_progress_army,
+                    vespene_used_in_progress_economy: player_stats_event
+                        .stats
+                        .vespene_used_in_progress_economy,
+                    vespene_used_in_progress_technology: player_stats_event
+                        .stats
+                        .vespene_used_in_progress_technology,
+                    minerals_used_current_army: player_stats_event.stats.minerals_used_current_army,
+                    minerals_used_current_economy: player_stats_event
+                        .stats
+                        .minerals_used_current_economy,
+                    minerals_used_current_technology: player_stats_event
+                        .stats
+                        .minerals_used_current_technology,
+                    vespene_used_current_army: player_stats_event.stats.vespene_used_current_army,
+                    vespene_used_current_economy: player_stats_event
+                        .stats
+                        .vespene_used_current_economy,
+                    vespene_used_current_technology: player_stats_event
+                        .stats
+                        .vespene_used_current_technology,
+                    minerals_friendly_fire_army: player_stats_event
+                        .stats
+                        .minerals_friendly_fire_army,
+                    minerals_friendly_fire_economy: player_stats_event
+                        .stats
+                        .minerals_friendly_fire_economy,
+                    minerals_friendly_fire_technology: player_stats_event
+                        .stats
+                        .minerals_friendly_fire_technology,
+                    vespene_friendly_fire_army: player_stats_event.stats.vespene_friendly_fire_army,
+                    vespene_friendly_fire_economy: player_stats_event
+                        .stats
+                        .vespene_friendly_fire_economy,
+                    vespene_friendly_fire_technology: player_stats_event
+                        .stats
+                        .vespene_friendly_fire_technology,
+                    minerals_lost_army: player_stats_event.stats.minerals_lost_army,
+                    minerals_lost_economy: player_stats_event.stats.minerals_lost_economy,
+                    minerals_lost_technology: player_stats_event.stats.minerals_lost_technology,
+                    vespene_lost_army: player_stats_event.stats.vespene_lost_army,
+                    vespene_lost_economy: player_stats_event.stats.vespene_lost_economy,
+                    vespene_lost_technology: player_stats_event.stats.vespene_lost_technology,
+                    minerals_killed_army: player_stats_event.stats.minerals_killed_army,
+                    minerals_killed_economy: player_stats_event.stats.minerals_killed_economy,
+                    minerals_killed_technology: player_stats_event.stats.minerals_killed_technology,
+                    vespene_killed_army: player_stats_event.stats.vespene_killed_army,
+                    vespene_killed_economy: player_stats_event.stats.vespene_killed_economy,
+                    vespene_killed_technology: player_stats_event.stats.vespene_killed_technology,
+                });
+            }
+            _ => {}
+        }
+    }
+    snapshots
+}
@@ -0,0 +1,228 @@
+//! Actions and effective actions per minute computed from the game events.
+//! Commands, selection changes and control group updates are counted as actions, an action is
+//! not effective when the user repeats the same action within `SPAM_WINDOW_LOOPS`, i.e. spamming
+//! right clicks or selecting the same units again. Actions are the same when they use the same
+//! ability, select the same units or update the same control group.
+
+use crate::GameSpeed;
+use s2protocol::game_events::{GameEvent, ReplayGameEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Repeating the same action within this many game loops is considered spam.
+const SPAM_WINDOW_LOOPS: u32 = 8;
+
+/// The length of the sliding window the APM samples are computed over.
+const WINDOW_SECONDS: f32 = 60.;
+
+/// The distance between APM samples.
+const STEP_SECONDS: f32 = 10.;
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub struct ApmSample {
+    /// The end of the sliding window.
+    pub frame: u32,
+    pub apm: f32,
+    pub epm: f32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PlayerApm {
+    /// The game events user id.
+    pub user_id: u32,
+    /// The number of actions over the whole game.
+    pub actions: usize,
+    pub effective_actions: usize,
+    /// APM and EPM over the whole game.
+    pub apm: f32,
+    pub epm: f32,
+    /// APM and EPM over sliding windows of `WINDOW_SECONDS`.
+    pub samples: Vec<ApmSample>,
+}
+
+/// What an action did, two actions with the same key are the same action.
+#[derive(Debug, PartialEq, Clone)]
+enum ActionKey {
+    /// The ability link and command index, None for the smart command (right click).
+    Cmd(Option<(u32, u32)>),
+    /// The control group whose selection changed and the units added to it.
+    SelectionDelta {
+        control_group_id: u8,
+        add_unit_tags: Vec<u32>,
+    },
+    /// The control group index.
+    ControlGroupUpdate(u8),
+}
+
+/// An action of a user, at the frame it was performed.
+struct UserAction {
+    frame: u32,
+    user_id: u32,
+    key: ActionKey,
+}
+
+/// An action, with whether it was effective.
+struct Action {
+    frame: u32,
+    effective: bool,
+}
+
+fn action_key(event: &ReplayGameEvent) -> Option<ActionKey> {
+    match event {
+        ReplayGameEvent::Cmd(cmd) => {
+            Some(ActionKey::Cmd(cmd.abil.as_ref().map(|abil| {
+                (abil.abil_link as u32, abil.abil_cmd_index as u32)
+            })))
+        }
+        ReplayGameEvent::SelectionDelta(selection_delta) => Some(ActionKey::SelectionDelta {
+            control_group_id: selection_delta.control_group_id as u8,
+            add_unit_tags: selection_delta
+                .delta
+                .add_unit_tags
+                .iter()
+                .map(|tag| *tag as u32)
+                .collect(),
+        }),
+        ReplayGameEvent::ControlGroupUpdate(update) => Some(ActionKey::ControlGroupUpdate(
+            update.control_group_index as u8,
+        )),
+        _ => None,
+    }
+}
+
+/// Computes the APM and EPM of every user that performed actions, ordered by user id.
+pub fn compute_apm(
+    game_events: &[GameEvent],
+    game_speed: GameSpeed,
+    last_frame: u32,
+) -> Vec<PlayerApm> {
+    let mut actions = vec![];
+    let mut frame = 0u32;
+    for event in game_events {
+        frame += event.delta as u32;
+        if let Some(key) = action_key(&event.event) {
+            actions.push(UserAction {
+                frame,
+                user_id: event.user_id as u32,
+                key,
+            });
+        }
+    }
+    compute_actions_apm(&actions, game_speed, last_frame)
+}
+
+/// Computes the APM and EPM of the actions, ordered by frame.
+fn compute_actions_apm(
+    actions: &[UserAction],
+    game_speed: GameSpeed,
+    last_frame: u32,
+) -> Vec<PlayerApm> {
+    let mut user_actions: BTreeMap<u32, Vec<Action>> = BTreeMap::new();
+    let mut previous_actions: BTreeMap<u32, &UserAction> = BTreeMap::new();
+    for action in actions {
+        let effective = match previous_actions.get(&action.user_id) {
+            Some(previous) => {
+                previous.key != action.key || action.frame - previous.frame > SPAM_WINDOW_LOOPS
+            }
+            None => true,
+        };
+        previous_actions.insert(action.user_id, action);
+        user_actions
+            .entry(action.user_id)
+            .or_default()
+            .push(Action {
+                frame: action.frame,
+                effective,
+            });
+    }
+    let minutes = (game_speed.loops_to_seconds(last_frame) / 60.).max(1. / 60.);
+    user_actions
+        .into_iter()
+        .map(|(user_id, actions)| {
+            let effective_actions = actions.iter().filter(|action| action.effective).count();
+            PlayerApm {
+                user_id,
+                actions: actions.len(),
+                effective_actions,
+                apm: actions.len() as f32 / minutes,
+                epm: effective_actions as f32 / minutes,
+                samples: sliding_window_samples(&actions, game_speed, last_frame),
+            }
+        })
+        .collect()
+}
+
+fn sliding_window_samples(
+    actions: &[Action],
+    game_speed: GameSpeed,
+    last_frame: u32,
+) -> Vec<ApmSample> {
+    let window = game_speed.seconds_to_loops(WINDOW_SECONDS);
+    let step = game_speed.seconds_to_loops(STEP_SECONDS).max(1);
+    let mut samples = vec![];
+    let mut end = step;
+    while end <= last_frame.max(step) {
+        let start = end.saturating_sub(window);
+        let window_minutes = game_speed.loops_to_seconds(end - start) / 60.;
+        let in_window = actions
+            .iter()
+            .filter(|action| action.frame > start && action.frame <= end);
+        let (count, effective) = in_window.fold((0, 0), |(count, effective), action| {
+            (count + 1, effective + usize::from(action.effective))
+        });
+        samples.push(ApmSample {
+            frame: end,
+            apm: count as f32 / window_minutes,
+            epm: effective as f32 / window_minutes,
+        });
+        end += step;
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(frame: u32, user_id: u32, key: ActionKey) -> UserAction {
+        UserAction {
+            frame,
+            user_id,
+            key,
+        }
+    }
+
+    fn select(unit_tags: &[u32]) -> ActionKey {
+        ActionKey::SelectionDelta {
+            control_group_id: 10,
+            add_unit_tags: unit_tags.to_vec(),
+        }
+    }
+
+    #[test]
+    fn repeated_actions_are_not_effective() {
+        let actions = vec![
+            action(10, 0, ActionKey::Cmd(Some((1, 0)))),
+            // The same ability again, spam.
+            action(14, 0, ActionKey::Cmd(Some((1, 0)))),
+            // Another ability right after, effective.
+            action(16, 0, ActionKey::Cmd(Some((2, 0)))),
+            action(100, 0, ActionKey::ControlGroupUpdate(1)),
+            action(102, 0, ActionKey::ControlGroupUpdate(2)),
+            // The same control group after the spam window.
+            action(111, 0, ActionKey::ControlGroupUpdate(2)),
+            action(200, 1, select(&[5, 6])),
+            action(205, 1, select(&[5, 6])),
+            action(208, 1, select(&[7])),
+        ];
+        // 16 loops per second at Normal speed, a game of one minute.
+        let apm = compute_actions_apm(&actions, GameSpeed::Normal, 960);
+        let counts: Vec<(u32, usize, usize)> = apm
+            .iter()
+            .map(|player| (player.user_id, player.actions, player.effective_actions))
+            .collect();
+        assert_eq!(counts, vec![(0, 6, 5), (1, 3, 2)]);
+        assert_eq!(apm[0].apm, 6.);
+        assert_eq!(apm[0].epm, 5.);
+    }
+}
@@ -0,0 +1,1617 @@
+use crate::aggregate::toons;
+use crate::canvas::{download_bytes, download_text};
+use crate::engagements::death_heatmap;
+use crate::export::{export_stem, to_csv};
+use crate::library::{
+    delete_replay, list_entries, load_replay_file, load_summary, open_library, store_replay,
+    LibraryEntry,
+};
+use crate::minimap::{Minimap, MinimapHeatCell, MinimapPoint};
+use crate::plot::{LineStyle, Plot, PlotData, PlotSeries};
+use crate::summary::is_summary_file_name;
+use crate::time::format_seconds;
+use crate::timeline::{Timeline, TimelineEvent, TimelineLane};
+use crate::PlotLayout;
+use crate::{
+    minor_player_clan_unescape, AggregateStats, ApmSample, ChatMessage, CsvTable, GameResult,
+    GameSnapshot, ProcessedReplay, ReplayError, ReplayPlayer, ReplaySummary, SnapshotMetric, Toon,
+    UnitLifecycle, WinRecord,
+};
+use gloo::file::callbacks::FileReader;
+use gloo::file::File;
+use gloo::render::{request_animation_frame, AnimationFrame};
+use gloo_console::log;
+use plotters::prelude::RGBColor;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use web_sys::{
+    DragEvent, Event, FileList, HtmlInputElement, HtmlSelectElement, IdbDatabase, MouseEvent,
+};
+use yew::events::InputEvent;
+use yew::html::TargetCast;
+use yew::{html, Callback, Component, Context, Html};
+
+pub enum Msg {
+    /// The file of the reader with the id was read.
+    Loaded(usize, String, Result<Vec<u8>, ReplayError>),
+    Files(Vec<File>),
+    /// The hovered frame of the plots of the replay at the index changed.
+    Cursor(usize, Option<u32>),
+    /// Apply the cursor moves received since the last animation frame.
+    CursorFrame,
+    /// The plots of the replay at the index were zoomed in, or reset when None.
+    Zoom(usize, Option<(u32, u32)>),
+    Layout(PlotLayout),
+    /// Replace the chart at the second index of the replay at the first index.
+    Chart(usize, usize, SnapshotChart),
+    AddChart(usize),
+    RemoveChart(usize, usize),
+    /// The minimap of the replay at the index shows the units at the frame.
+    MinimapFrame(usize, u32),
+    /// The minimap of the replay at the index shows where the units of the player died, or no
+    /// heatmap when None.
+    HeatmapPlayer(usize, Option<u8>),
+    /// Download the data of the replay at the index.
+    Export(usize, ExportFormat),
+    ViewMode(ViewMode),
+    /// Add or remove a player from the comparison.
+    ToggleCompared(ComparedPlayer),
+    /// The hovered frame of the comparison plots changed.
+    ComparisonCursor(Option<u32>),
+    /// The comparison plots were zoomed in, or reset when None.
+    ComparisonZoom(Option<(u32, u32)>),
+    /// Restrict the dashboard to the games of an account, or show every player when None.
+    DashboardToon(Option<Toon>),
+    LibraryOpened(Result<IdbDatabase, ReplayError>),
+    LibraryListed(Result<Vec<LibraryEntry>, ReplayError>),
+    /// Load the replay stored under the key.
+    LibraryOpen(String),
+    LibraryLoaded(String, Result<ReplaySummary, ReplayError>),
+    LibraryDelete(String),
+    /// Download the SC2Replay file stored under the key.
+    LibraryDownload(String),
+    LibraryReplayFile(String, Result<Vec<u8>, ReplayError>),
+    /// Whether the SC2Replay files are stored in the library along with their summaries.
+    KeepReplayFiles(bool),
+    DismissNotice(usize),
+}
+
+/// Whether the replays are shown one after another or the selected players are overlaid on
+/// shared plots.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum ViewMode {
+    #[default]
+    Replays,
+    Comparison,
+    /// Statistics over all the loaded replays.
+    Dashboard,
+    /// The replays stored in the browser.
+    Library,
+    /// The units of the loaded replays that were initialized but never completed.
+    UnitsInit,
+    /// The units of the loaded replays that died, by death frame.
+    UnitsDead,
+}
+
+/// A player of a loaded replay, the `player_id` is the tracker events player id.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct ComparedPlayer {
+    replay_idx: usize,
+    player_id: u8,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ExportFormat {
+    Json,
+    Csv(CsvTable),
+}
+
+/// A processed replay along with the state of its plots.
+pub struct LoadedReplay {
+    replay: ProcessedReplay,
+    /// The hovered frame, shared by all the plots of the replay.
+    cursor: Option<u32>,
+    /// The zoomed in range of frames, shared by all the plots of the replay.
+    x_range: Option<(u32, u32)>,
+    /// The frame the minimap shows the units at.
+    minimap_frame: u32,
+    /// The tracker events player id of the player the death heatmap is shown for.
+    heatmap_player: Option<u8>,
+    charts: Vec<SnapshotChart>,
+}
+
+/// A plot of a metric of the snapshots of every player, the `secondary` metric is drawn lighter
+/// and dashed, i.e. the vespene income next to the mineral income.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SnapshotChart {
+    primary: SnapshotMetric,
+    secondary: Option<SnapshotMetric>,
+}
+
+impl SnapshotChart {
+    fn title(&self) -> String {
+        match self.secondary {
+            Some(secondary) => format!("{} / {}", self.primary, secondary),
+            None => self.primary.to_string(),
+        }
+    }
+}
+
+/// The charts of a replay until they are changed with the metric pickers.
+const DEFAULT_CHARTS: [SnapshotChart; 6] = [
+    SnapshotChart {
+        primary: SnapshotMetric::MineralIncome,
+        secondary: Some(SnapshotMetric::VespeneIncome),
+    },
+    SnapshotChart {
+        primary: SnapshotMetric::SupplyWorkers,
+        secondary: None,
+    },
+    SnapshotChart {
+        primary: SnapshotMetric::Minerals,
+        secondary: Some(SnapshotMetric::Vespene),
+    },
+    SnapshotChart {
+        primary: SnapshotMetric::SupplyUsed,
+        secondary: Some(SnapshotMetric::SupplyAvailable),
+    },
+    SnapshotChart {
+        primary: SnapshotMetric::ArmyValue,
+        secondary: None,
+    },
+    SnapshotChart {
+        primary: SnapshotMetric::ResourcesLost,
+        secondary: Some(SnapshotMetric::ResourcesKilled),
+    },
+];
+
+/// A file that could not be turned into a [`ProcessedReplay`], shown as an error card.
+pub struct FailedReplay {
+    name: String,
+    error: ReplayError,
+}
+
+pub struct App {
+    /// Keyed by an id rather than the file name, different files can have the same name.
+    readers: HashMap<usize, FileReader>,
+    next_reader_id: usize,
+    files: Vec<LoadedReplay>,
+    failures: Vec<FailedReplay>,
+    layout: PlotLayout,
+    view_mode: ViewMode,
+    /// The players overlaid in the comparison view, ordered by replay.
+    compared: BTreeSet<ComparedPlayer>,
+    comparison_cursor: Option<u32>,
+    comparison_x_range: Option<(u32, u32)>,
+    /// The mouse moves faster than the plots can be drawn, the cursor moves are kept here and
+    /// applied once per animation frame, the last one of each replay wins.
+    pending_cursors: BTreeMap<usize, Option<u32>>,
+    pending_comparison_cursor: Option<Option<u32>>,
+    /// The animation frame the pending cursors are applied on, cancelled when dropped.
+    cursor_frame: Option<AnimationFrame>,
+    dashboard_toon: Option<Toon>,
+    /// None until the IndexedDB database is opened, or when it is not available.
+    library: Option<IdbDatabase>,
+    library_entries: Vec<LibraryEntry>,
+    library_error: Option<ReplayError>,
+    keep_replay_files: bool,
+    /// Informational messages, i.e. a replay that was not loaded again.
+    notices: Vec<String>,
+}
+
+/// The side of the death heatmap squares, in map cells.
+const HEATMAP_CELL_SIZE: u32 = 4;
+
+/// The comparison view mixes players of several replays, so the in-game colors would clash.
+const COMPARISON_COLORS: [RGBColor; 8] = [
+    RGBColor(31, 119, 180),
+    RGBColor(214, 39, 40),
+    RGBColor(44, 160, 44),
+    RGBColor(255, 127, 14),
+    RGBColor(148, 103, 189),
+    RGBColor(140, 86, 75),
+    RGBColor(227, 119, 194),
+    RGBColor(23, 190, 207),
+];
+
+impl Component for App {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link()
+            .send_future(async { Msg::LibraryOpened(open_library().await) });
+        Self {
+            readers: HashMap::default(),
+            next_reader_id: 0,
+            files: Vec::default(),
+            failures: Vec::default(),
+            layout: PlotLayout::default(),
+            view_mode: ViewMode::default(),
+            compared: BTreeSet::default(),
+            comparison_cursor: None,
+            comparison_x_range: None,
+            pending_cursors: BTreeMap::new(),
+            pending_comparison_cursor: None,
+            cursor_frame: None,
+            dashboard_toon: None,
+            library: None,
+            library_entries: Vec::default(),
+            library_error: None,
+            keep_replay_files: true,
+            notices: Vec::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Loaded(reader_id, file_name, data) => {
+                let processed = data.and_then(|data| {
+                    let replay = ProcessedReplay::from_file(&file_name, &data)?;
+                    Ok((replay, data))
+                });
+                match processed {
+                    Ok((replay, data)) => {
+                        if !self.is_duplicate(&replay) {
+                            let replay_file =
+                                if self.keep_replay_files && !is_summary_file_name(&file_name) {
+                                    Some(data)
+                                } else {
+                                    None
+                                };
+                            self.store_in_library(ctx, &replay, replay_file);
+                            self.files.push(LoadedReplay {
+                                replay,
+                                cursor: None,
+                                x_range: None,
+                                minimap_frame: 0,
+                                heatmap_player: None,
+                                charts: DEFAULT_CHARTS.to_vec(),
+                            });
+                        }
+                    }
+                    Err(error) => {
+                        log!("Unable to process SC2Replay", error.to_string());
+                        self.failures.push(FailedReplay {
+                            name: file_name.clone(),
+                            error,
+                        });
+                    }
+                }
+                self.readers.remove(&reader_id);
+                true
+            }
+            Msg::Cursor(idx, cursor) => {
+                self.pending_cursors.insert(idx, cursor);
+                self.request_cursor_frame(ctx);
+                false
+            }
+            Msg::CursorFrame => {
+                self.cursor_frame = None;
+                let mut changed = false;
+                for (idx, cursor) in std::mem::take(&mut self.pending_cursors) {
+                    if let Some(loaded) = self.files.get_mut(idx) {
+                        changed |= loaded.cursor != cursor;
+                        loaded.cursor = cursor;
+                    }
+                }
+                if let Some(cursor) = self.pending_comparison_cursor.take() {
+                    changed |= self.comparison_cursor != cursor;
+                    self.comparison_cursor = cursor;
+                }
+                changed
+            }
+            Msg::Zoom(idx, x_range) => match self.files.get_mut(idx) {
+                Some(loaded) => {
+                    loaded.x_range = x_range;
+                    true
+                }
+                None => false,
+            },
+            Msg::MinimapFrame(idx, frame) => match self.files.get_mut(idx) {
+                Some(loaded) if loaded.minimap_frame != frame => {
+                    loaded.minimap_frame = frame;
+                    true
+                }
+                _ => false,
+            },
+            Msg::Chart(idx, chart_idx, chart) => {
+                match self
+                    .files
+                    .get_mut(idx)
+                    .and_then(|loaded| loaded.charts.get_mut(chart_idx))
+                {
+                    Some(current) => {
+                        *current = chart;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Msg::AddChart(idx) => match self.files.get_mut(idx) {
+                Some(loaded) => {
+                    loaded.charts.push(SnapshotChart {
+                        primary: SnapshotMetric::ArmyValue,
+                        secondary: None,
+                    });
+                    true
+                }
+                None => false,
+            },
+            Msg::RemoveChart(idx, chart_idx) => match self.files.get_mut(idx) {
+                Some(loaded) if chart_idx < loaded.charts.len() => {
+                    loaded.charts.remove(chart_idx);
+                    true
+                }
+                _ => false,
+            },
+            Msg::HeatmapPlayer(idx, player_id) => match self.files.get_mut(idx) {
+                Some(loaded) => {
+                    loaded.heatmap_player = player_id;
+                    true
+                }
+                None => false,
+            },
+            Msg::Layout(layout) => {
+                self.layout = layout;
+                true
+            }
+            Msg::ViewMode(view_mode) => {
+                self.view_mode = view_mode;
+                true
+            }
+            Msg::ToggleCompared(player) => {
+                if !self.compared.remove(&player) {
+                    self.compared.insert(player);
+                }
+                true
+            }
+            Msg::ComparisonCursor(cursor) => {
+                self.pending_comparison_cursor = Some(cursor);
+                self.request_cursor_frame(ctx);
+                false
+            }
+            Msg::ComparisonZoom(x_range) => {
+                self.comparison_x_range = x_range;
+                true
+            }
+            Msg::DashboardToon(toon) => {
+                self.dashboard_toon = toon;
+                true
+            }
+            Msg::LibraryOpened(library) => {
+                match library {
+                    Ok(db) => {
+                        self.library = Some(db.clone());
+                        ctx.link().send_future(async move {
+                            Msg::LibraryListed(list_entries(&db).await)
+                        });
+                    }
+                    Err(error) => {
+                        log!("Unable to open the replay library", error.to_string());
+                        self.library_error = Some(error);
+                    }
+                }
+                true
+            }
+            Msg::LibraryListed(entries) => {
+                match entries {
+                    Ok(entries) => self.library_entries = entries,
+                    Err(error) => self.library_error = Some(error),
+                }
+                true
+            }
+            Msg::LibraryOpen(key) => {
+                if let Some(db) = self.library.clone() {
+                    ctx.link().send_future(async move {
+                        let summary = load_summary(&db, &key).await;
+                        Msg::LibraryLoaded(key, summary)
+                    });
+                }
+                false
+            }
+            Msg::LibraryLoaded(key, summary) => {
+                match summary.map(ProcessedReplay::from_summary) {
+                    Ok(replay) => {
+                        if !self.is_duplicate(&replay) {
+                            self.files.push(LoadedReplay {
+                                replay,
+                                cursor: None,
+                                x_range: None,
+                                minimap_frame: 0,
+                                heatmap_player: None,
+                                charts: DEFAULT_CHARTS.to_vec(),
+                            });
+                        }
+                    }
+                    Err(error) => self.failures.push(FailedReplay { name: key, error }),
+                }
+                self.view_mode = ViewMode::Replays;
+                true
+            }
+            Msg::LibraryDelete(key) => {
+                if let Some(db) = self.library.clone() {
+                    ctx.link().send_future(async move {
+                        if let Err(error) = delete_replay(&db, &key).await {
+                            return Msg::LibraryListed(Err(error));
+                        }
+                        Msg::LibraryListed(list_entries(&db).await)
+                    });
+                }
+                false
+            }
+            Msg::LibraryDownload(key) => {
+                if let Some(db) = self.library.clone() {
+                    ctx.link().send_future(async move {
+                        let data = load_replay_file(&db, &key).await;
+                        Msg::LibraryReplayFile(key, data)
+                    });
+                }
+                false
+            }
+            Msg::LibraryReplayFile(key, data) => match data {
+                Ok(data) => {
+                    let file_name = self
+                        .library_entries
+                        .iter()
+                        .find(|entry| entry.key == key)
+                        .map(|entry| entry.name.clone())
+                        .unwrap_or_else(|| format!("{}.SC2Replay", key));
+                    download_bytes(&file_name, "application/octet-stream", &data);
+                    false
+                }
+                Err(error) => {
+                    self.library_error = Some(error);
+                    true
+                }
+            },
+            Msg::KeepReplayFiles(keep_replay_files) => {
+                self.keep_replay_files = keep_replay_files;
+                true
+            }
+            Msg::DismissNotice(idx) => {
+                if idx < self.notices.len() {
+                    self.notices.remove(idx);
+                }
+                true
+            }
+            Msg::Export(idx, format) => {
+                if let Some(loaded) = self.files.get(idx) {
+                    let summary = ReplaySummary::new(&loaded.replay);
+                    let stem = export_stem(&loaded.replay.name);
+                    match format {
+                        ExportFormat::Json => match summary.to_json() {
+                            Ok(summary_json) => download_text(
+                                &format!("{}.json", stem),
+                                "application/json",
+                                &summary_json,
+                            ),
+                            Err(err) => {
+                                self.notices
+                                    .push(format!("{}: {}", loaded.replay.name, err));
+                                return true;
+                            }
+                        },
+                        ExportFormat::Csv(table) => download_text(
+                            &format!("{}.{}.csv", stem, table),
+                            "text/csv",
+                            &to_csv(&summary, table),
+                        ),
+                    }
+                }
+                false
+            }
+            Msg::Files(files) => {
+                for file in files.into_iter() {
+                    let file_name = file.name();
+                    let reader_id = self.next_reader_id;
+                    self.next_reader_id += 1;
+
+                    let task = {
+                        let link = ctx.link().clone();
+
+                        gloo::file::callbacks::read_as_bytes(&file, move |res| {
+                            link.send_message(Msg::Loaded(
+                                reader_id,
+                                file_name,
+                                res.map_err(|err| ReplayError::Read(err.to_string())),
+                            ))
+                        })
+                    };
+                    self.readers.insert(reader_id, task);
+                }
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+        <main>
+        <nav class="navbar navbar-expand-lg bg-body-tertiary">
+          <div class="container-fluid">
+            <a class="navbar-brand" href="#">{ "Online SC2Replay Analyser " }</a>
+            <button class="navbar-toggler" type="button" data-bs-toggle="collapse" data-bs-target="#navbarSupportedContent" aria-controls="navbarSupportedContent" aria-expanded="false" aria-label="Toggle navigation">
+              <span class="navbar-toggler-icon"></span>
+            </button>
+            <div class="collapse navbar-collapse" id="navbarSupportedContent">
+              <ul class="navbar-nav me-auto mb-2 mb-lg-0">
+                <li class="nav-item">
+                  <a class="nav-link active" aria-current="page" href="#">{ "Overview" }</a>
+                </li>
+                <li class="nav-item">
+                  <a class="nav-link" href="#">{" Messages "}</a>
+                </li>
+                <li class="nav-item dropdown">
+                  <a class="nav-link dropdown-toggle" href="#" role="button" data-bs-toggle="dropdown" aria-expanded="false">
+                  { "Units" }
+                  </a>
+                  <ul class="dropdown-menu">
+                    <li><a class="dropdown-item" href="#">{ "Supply" }</a></li>
+                    <li><a class="dropdown-item" href="#" onclick={ctx.link().callback(|event: MouseEvent| {
+                        event.prevent_default();
+                        Msg::ViewMode(ViewMode::UnitsInit)
+                    })}>{ "Init" }</a></li>
+                    <li><a class="dropdown-item" href="#" onclick={ctx.link().callback(|event: MouseEvent| {
+                        event.prevent_default();
+                        Msg::ViewMode(ViewMode::UnitsDead)
+                    })}>{ "Dead" }</a></li>
+                  </ul>
+                </li>
+                <li class="nav-item">
+                  <a class="nav-link disabled" aria-disabled="true">{ "Stats" }</a>
+                </li>
+              </ul>
+                    <div class="btn-group btn-group-sm me-2" role="group" aria-label="View mode">
+                      <button type="button"
+                        class={ if self.view_mode == ViewMode::Replays { "btn btn-secondary active" } else { "btn btn-secondary" } }
+                        onclick={ctx.link().callback(|_| Msg::ViewMode(ViewMode::Replays))}>
+                        { "Replays" }
+                      </button>
+                      <button type="button"
+                        class={ if self.view_mode == ViewMode::Comparison { "btn btn-secondary active" } else { "btn btn-secondary" } }
+                        onclick={ctx.link().callback(|_| Msg::ViewMode(ViewMode::Comparison))}>
+                        { "Compare" }
+                      </button>
+                      <button type="button"
+                        class={ if self.view_mode == ViewMode::Dashboard { "btn btn-secondary active" } else { "btn btn-secondary" } }
+                        onclick={ctx.link().callback(|_| Msg::ViewMode(ViewMode::Dashboard))}>
+                        { "Dashboard" }
+                      </button>
+                      <button type="button"
+                        class={ if self.view_mode == ViewMode::Library { "btn btn-secondary active" } else { "btn btn-secondary" } }
+                        onclick={ctx.link().callback(|_| Msg::ViewMode(ViewMode::Library))}>
+                        { format!("Library ({})", self.library_entries.len()) }
+                      </button>
+                    </div>
+                    <div class="btn-group btn-group-sm me-2" role="group" aria-label="Plot layout">
+                      <button type="button"
+                        class={ if self.layout == PlotLayout::Compact { "btn btn-secondary active" } else { "btn btn-secondary" } }
+                        onclick={ctx.link().callback(|_| Msg::Layout(PlotLayout::Compact))}>
+                        { "Compact" }
+                      </button>
+                      <button type="button"
+                        class={ if self.layout == PlotLayout::FullWidth { "btn btn-secondary active" } else { "btn btn-secondary" } }
+                        onclick={ctx.link().callback(|_| Msg::Layout(PlotLayout::FullWidth))}>
+                        { "Full width" }
+                      </button>
+                    </div>
+                    <label for="file-upload">
+                        <div
+                            id="drop-container"
+                            class="mb-1"
+                            ondrop={ctx.link().callback(|event: DragEvent| {
+                                event.prevent_default();
+                                let files = event.data_transfer().and_then(|data| data.files());
+                                Self::upload_files(files)
+                            })}
+                            ondragover={Callback::from(|event: DragEvent| {
+                                event.prevent_default();
+                            })}
+                            ondragenter={Callback::from(|event: DragEvent| {
+                                event.prevent_default();
+                            })}
+                        >
+                            <i class="fa fa-cloud-upload"></i>
+                        </div>
+                    </label>
+                    <div class="input-group mb-1">
+                        <input
+                            class="form-control"
+                            id="file-upload"
+                            type="file"
+                            accept=".SC2Replay,.json"
+                            multiple={true}
+                            onchange={ctx.link().callback(move |e: Event| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Self::upload_files(input.files())
+                            })}
+                        />
+                    </div>
+            </div>
+          </div>
+        </nav>
+        <div class={ self.container_class() }>
+            { for self.notices.iter().enumerate().map(|(idx, notice)| html! {
+                <div class="alert alert-warning alert-dismissible m-1" role="alert">
+                  { notice }
+                  <button type="button" class="btn-close" aria-label="Close"
+                    onclick={ctx.link().callback(move |_| Msg::DismissNotice(idx))}></button>
+                </div>
+            }) }
+            { for self.failures.iter().map(Self::view_failed_replay) }
+            { match self.view_mode {
+                ViewMode::Replays => html! {
+                  <>
+                  { for self.files.iter().enumerate().map(|(idx, loaded)| self.view_details(ctx, idx, loaded)) }
+                  </>
+                },
+                ViewMode::Comparison => self.view_comparison(ctx),
+                ViewMode::Dashboard => self.view_dashboard(ctx),
+                ViewMode::Library => self.view_library(ctx),
+                ViewMode::UnitsInit => self.view_unit_lists(|replay| replay.units.never_completed().collect()),
+                ViewMode::UnitsDead => self.view_unit_lists(|replay| replay.units.dead()),
+            } }
+        </div>
+        </main>
+         }
+    }
+}
+
+impl App {
+    /// Requests the animation frame the pending cursor moves are applied on, unless one already
+    /// is.
+    fn request_cursor_frame(&mut self, ctx: &Context<Self>) {
+        if self.cursor_frame.is_none() {
+            let link = ctx.link().clone();
+            self.cursor_frame = Some(request_animation_frame(move |_| {
+                link.send_message(Msg::CursorFrame)
+            }));
+        }
+    }
+
+    /// The full width layout lets the plots take the whole page.
+    fn container_class(&self) -> &'static str {
+        match self.layout {
+            PlotLayout::Compact => "container",
+            PlotLayout::FullWidth => "container-fluid",
+        }
+    }
+
+    /// Overlays the curves of the selected players of any of the loaded replays on shared plots.
+    /// Game loops are the same simulation step at every game speed, so the replays are aligned on
+    /// their frames.
+    fn view_comparison(&self, ctx: &Context<Self>) -> Html {
+        let on_cursor = ctx.link().callback(Msg::ComparisonCursor);
+        let on_zoom = ctx.link().callback(Msg::ComparisonZoom);
+        let plots = if self.compared.len() < 2 {
+            html! {
+              <div class="row">
+                <div class="col alert alert-info">{ "Select two or more players to compare." }</div>
+              </div>
+            }
+        } else {
+            let income_series = self.comparison_series(|s| s.mineral_income + s.vespene_income);
+            let worker_series = self.comparison_series(|s| s.supply_workers);
+            let army_value_series =
+                self.comparison_series(|s| s.active_force_minerals + s.active_force_vespene);
+            html! {
+              <>
+                <div class="row">
+                <div class="col"><h2>{ "Income" }</h2></div>
+                </div>
+                <div class="row">
+                  <Plot series={income_series} export_name="comparison Income" y_desc="Collection rate (per minute)" cursor={self.comparison_cursor} on_cursor={on_cursor.clone()} x_range={self.comparison_x_range} on_zoom={on_zoom.clone()} layout={self.layout} />
+                </div>
+                <div class="row">
+                <div class="col"><h2>{ "Workers" }</h2></div>
+                </div>
+                <div class="row">
+                  <Plot series={worker_series} export_name="comparison Workers" y_desc="Workers" cursor={self.comparison_cursor} on_cursor={on_cursor.clone()} x_range={self.comparison_x_range} on_zoom={on_zoom.clone()} layout={self.layout} />
+                </div>
+                <div class="row">
+                <div class="col"><h2>{ "Army Value" }</h2></div>
+                </div>
+                <div class="row">
+                  <Plot series={army_value_series} export_name="comparison Army Value" y_desc="Army value (resources)" cursor={self.comparison_cursor} on_cursor={on_cursor} x_range={self.comparison_x_range} on_zoom={on_zoom} layout={self.layout} />
+                </div>
+              </>
+            }
+        };
+        html! {
+            <div class={ format!("{} text-center", self.container_class()) }>
+              <div class="row">
+                { for self.files.iter().enumerate().map(|(replay_idx, loaded)| html! {
+                    <div class="col card m-1 text-start">
+                      <div class="card-header" title={ loaded.replay.date.format_utc() }>
+                        { &loaded.replay.name }
+                      </div>
+                      <div class="card-body">
+                        { for Self::players_by_team(&loaded.replay).map(|(player_id, player)| {
+                            let compared = ComparedPlayer { replay_idx, player_id };
+                            let checkbox_id = format!("compare-{}-{}", replay_idx, player_id);
+                            html! {
+                              <div class="form-check">
+                                <input class="form-check-input" type="checkbox" id={ checkbox_id.clone() }
+                                  checked={ self.compared.contains(&compared) }
+                                  onchange={ctx.link().callback(move |_| Msg::ToggleCompared(compared))} />
+                                <label class="form-check-label" for={ checkbox_id }>
+                                  { format!("{} ({})", minor_player_clan_unescape(&player.name), player.race) }
+                                </label>
+                              </div>
+                            }
+                        }) }
+                      </div>
+                    </div>
+                }) }
+              </div>
+              { plots }
+            </div>
+        }
+    }
+
+    /// Whether the replay, or another copy of the same game, is already loaded. Duplicates are
+    /// reported as a notice.
+    fn is_duplicate(&mut self, replay: &ProcessedReplay) -> bool {
+        let loaded = self
+            .files
+            .iter()
+            .find(|loaded| loaded.replay.identity.is_same_game(&replay.identity));
+        match loaded {
+            Some(loaded) => {
+                let notice = if loaded.replay.identity.content_hash == replay.identity.content_hash
+                {
+                    format!(
+                        "{} is already loaded as {}.",
+                        replay.name, loaded.replay.name
+                    )
+                } else {
+                    format!(
+                        "{} is another copy of the game already loaded as {}.",
+                        replay.name, loaded.replay.name
+                    )
+                };
+                self.notices.push(notice);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Persists a newly loaded replay in the library, then refreshes the library panel.
+    fn store_in_library(
+        &self,
+        ctx: &Context<Self>,
+        replay: &ProcessedReplay,
+        replay_file: Option<Vec<u8>>,
+    ) {
+        let db = match &self.library {
+            Some(db) => db.clone(),
+            None => return,
+        };
+        let entry = LibraryEntry::new(replay, replay_file.is_some());
+        let summary = ReplaySummary::new(replay);
+        ctx.link().send_future(async move {
+            if let Err(error) = store_replay(&db, &entry, &summary, replay_file.as_deref()).await {
+                return Msg::LibraryListed(Err(error));
+            }
+            Msg::LibraryListed(list_entries(&db).await)
+        });
+    }
+
+    /// Lists the replays stored in the browser, most recent first.
+    fn view_library(&self, ctx: &Context<Self>) -> Html {
+        let error = match &self.library_error {
+            Some(error) => html! {
+              <div class="row">
+                <div class="col alert alert-danger">{ error.to_string() }</div>
+              </div>
+            },
+            None => html! {},
+        };
+        html! {
+            <div class={ format!("{} text-center", self.container_class()) }>
+              { error }
+              <div class="row">
+                <div class="col form-check text-start">
+                  <input class="form-check-input" type="checkbox" id="keep-replay-files"
+                    checked={ self.keep_replay_files }
+                    onchange={ctx.link().callback(|e: Event| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        Msg::KeepReplayFiles(input.checked())
+                    })} />
+                  <label class="form-check-label" for="keep-replay-files">
+                    { "Keep the SC2Replay files of new replays" }
+                  </label>
+                </div>
+              </div>
+              <div class="row">
+                <div class="col">
+                <table class="table table-sm table-striped text-start">
+                  <thead>
+                    <tr>
+                      <th scope="col">{ "Date" }</th>
+                      <th scope="col">{ "Map" }</th>
+                      <th scope="col">{ "Players" }</th>
+                      <th scope="col">{ "Duration" }</th>
+                      <th scope="col"></th>
+                    </tr>
+                  </thead>
+                  <tbody>
+                  { for self.library_entries.iter().map(|entry| Self::view_library_entry(ctx, entry)) }
+                  </tbody>
+                </table>
+                </div>
+              </div>
+            </div>
+        }
+    }
+
+    fn view_library_entry(ctx: &Context<Self>, entry: &LibraryEntry) -> Html {
+        let mut teams: BTreeMap<u8, Vec<Html>> = BTreeMap::new();
+        for player in &entry.players {
+            let class = match player.result {
+                GameResult::Win => "text-success",
+                GameResult::Loss => "text-danger",
+                GameResult::Tie | GameResult::Undecided => "",
+            };
+            teams.entry(player.team_id).or_default().push(html! {
+                <span class={ class } title={ player.result.to_string() }>
+                  { format!("{} ({}) ", player.name, player.race) }
+                </span>
+            });
+        }
+        let teams = teams.into_values().enumerate().map(|(idx, players)| {
+            html! {
+              <>
+                if idx > 0 { { "vs " } }
+                { for players }
+              </>
+            }
+        });
+        let open_key = entry.key.clone();
+        let download_key = entry.key.clone();
+        let delete_key = entry.key.clone();
+        html! {
+            <tr>
+              <td title={ entry.date.format_utc() }>{ entry.date.format_local() }</td>
+              <td>{ &entry.map }</td>
+              <td title={ entry.name.clone() }>{ for teams }</td>
+              <td>{ format_seconds(entry.duration_seconds) }</td>
+              <td class="text-end">
+                <div class="btn-group btn-group-sm" role="group" aria-label="Library replay">
+                  <button type="button" class="btn btn-outline-primary"
+                    onclick={ctx.link().callback(move |_| Msg::LibraryOpen(open_key.clone()))}>{ "Open" }</button>
+                  if entry.has_replay_file {
+                    <button type="button" class="btn btn-outline-secondary"
+                      onclick={ctx.link().callback(move |_| Msg::LibraryDownload(download_key.clone()))}>{ "SC2Replay" }</button>
+                  }
+                  <button type="button" class="btn btn-outline-danger"
+                    onclick={ctx.link().callback(move |_| Msg::LibraryDelete(delete_key.clone()))}>{ "Delete" }</button>
+                </div>
+              </td>
+            </tr>
+        }
+    }
+
+    /// Lists the `units` of every loaded replay, one table per replay.
+    fn view_unit_lists(&self, units: impl Fn(&ProcessedReplay) -> Vec<&UnitLifecycle>) -> Html {
+        html! {
+            <>
+            { for self.files.iter().map(|loaded| {
+                let replay = &loaded.replay;
+                let listed = units(replay);
+                html! {
+                  <>
+                    <div class="row">
+                      <div class="col"><h2>{ &replay.name }</h2></div>
+                    </div>
+                    <div class="row">
+                      <div class="col">
+                        if listed.is_empty() {
+                          <div class="alert alert-info">{ "No units." }</div>
+                        } else {
+                          { Self::view_unit_table(replay, &listed) }
+                        }
+                      </div>
+                    </div>
+                  </>
+                }
+            }) }
+            </>
+        }
+    }
+
+    fn view_unit_table(replay: &ProcessedReplay, units: &[&UnitLifecycle]) -> Html {
+        let optional_frame = |frame: Option<u32>| match frame {
+            Some(frame) => replay.format_frame(frame),
+            None => "-".to_string(),
+        };
+        html! {
+            <table class="table table-sm table-striped text-start">
+              <thead>
+                <tr>
+                  <th>{ "Unit" }</th>
+                  <th>{ "Player" }</th>
+                  <th>{ "Created" }</th>
+                  <th>{ "Completed" }</th>
+                  <th>{ "Died" }</th>
+                  <th>{ "Killed by" }</th>
+                </tr>
+              </thead>
+              <tbody>
+                { for units.iter().map(|unit| html! {
+                    <tr>
+                      <td title={ format!("tag: {}-{}", unit.tag.index, unit.tag.recycle) }>{ unit.current_type_name() }</td>
+                      <td>{ replay.roster.name_by_player_id(unit.owner) }</td>
+                      <td title={ format!("frame: {}", unit.creation_frame) }>{ replay.format_frame(unit.creation_frame) }</td>
+                      <td>{ optional_frame(unit.completion_frame) }</td>
+                      <td>{ optional_frame(unit.death_frame) }</td>
+                      <td>{ unit.killer_player_id.map(|player_id| replay.roster.name_by_player_id(player_id)).unwrap_or_else(|| "-".to_string()) }</td>
+                    </tr>
+                }) }
+              </tbody>
+            </table>
+        }
+    }
+
+    /// Displays win rates and economy statistics over all the loaded replays.
+    fn view_dashboard(&self, ctx: &Context<Self>) -> Html {
+        let toons: Vec<(Toon, String)> = toons(self.files.iter().map(|loaded| &loaded.replay))
+            .into_iter()
+            .collect();
+        let stats = AggregateStats::new(
+            self.files.iter().map(|loaded| &loaded.replay),
+            self.dashboard_toon,
+        );
+        let on_toon_change = {
+            let toons: Vec<Toon> = toons.iter().map(|(toon, _)| *toon).collect();
+            ctx.link().callback(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                let value = select.value();
+                Msg::DashboardToon(toons.iter().find(|toon| toon.to_string() == value).copied())
+            })
+        };
+        let optional_seconds = |seconds: Option<f32>| match seconds {
+            Some(seconds) => format_seconds(seconds.round() as u32),
+            None => "-".to_string(),
+        };
+        html! {
+            <div class={ format!("{} text-center", self.container_class()) }>
+              <div class="row">
+                <div class="col-4">
+                  <select class="form-select" onchange={on_toon_change}>
+                    <option value="" selected={ self.dashboard_toon.is_none() }>{ "All players" }</option>
+                    { for toons.iter().map(|(toon, name)| html! {
+                        <option value={ toon.to_string() } selected={ self.dashboard_toon == Some(*toon) }>
+                          { format!("{} ({})", name, toon) }
+                        </option>
+                    }) }
+                  </select>
+                </div>
+              </div>
+              <div class="row">
+                <div class="col"><h6>{ "Replays" }</h6>{ stats.replays }</div>
+                <div class="col"><h6>{ "Player games" }</h6>{ stats.player_games }</div>
+                <div class="col"><h6>{ "Average game length" }</h6>{ optional_seconds(stats.average_game_seconds) }</div>
+                <div class="col"><h6>{ "Average workers at 6:00" }</h6>
+                  { stats.average_workers_at_six.map(|workers| format!("{:.1}", workers)).unwrap_or_else(|| "-".to_string()) }
+                </div>
+                <div class="col"><h6>{ "Median time to 66 workers" }</h6>{ optional_seconds(stats.median_seconds_to_saturation) }</div>
+              </div>
+              <div class="row">
+                <div class="col">{ Self::view_win_records("Race", &stats.by_race) }</div>
+                <div class="col">{ Self::view_win_records("Matchup", &stats.by_matchup) }</div>
+                <div class="col">{ Self::view_win_records("Map", &stats.by_map) }</div>
+              </div>
+            </div>
+        }
+    }
+
+    fn view_win_records<'a>(
+        title: &str,
+        records: impl IntoIterator<Item = (&'a String, &'a WinRecord)>,
+    ) -> Html {
+        html! {
+            <table class="table table-sm table-striped text-start">
+              <thead>
+                <tr>
+                  <th scope="col">{ title }</th>
+                  <th scope="col">{ "Games" }</th>
+                  <th scope="col">{ "W-L" }</th>
+                  <th scope="col">{ "Win rate" }</th>
+                </tr>
+              </thead>
+              <tbody>
+              { for records.into_iter().map(|(key, record)| html! {
+                <tr>
+                  <td>{ key }</td>
+                  <td>{ record.games() }</td>
+                  <td>{ format!("{}-{}", record.wins, record.losses) }</td>
+                  <td>{ format!("{:.0}%", record.win_rate() * 100.) }</td>
+                </tr>
+              }) }
+              </tbody>
+            </table>
+        }
+    }
+
+    /// Creates one series per compared player with the metric returned by `value`. Players of the
+    /// first compared replay, the reference game, are drawn solid and the others dashed.
+    fn comparison_series(&self, value: impl Fn(&GameSnapshot) -> i32) -> Vec<PlotSeries> {
+        let reference_idx = self.compared.iter().next().map(|player| player.replay_idx);
+        self.compared
+            .iter()
+            .enumerate()
+            .filter_map(|(series_idx, compared)| {
+                let replay = &self.files.get(compared.replay_idx)?.replay;
+                let player_name = replay.roster.name_by_player_id(compared.player_id);
+                Some(PlotSeries {
+                    series: replay
+                        .game_snapshots
+                        .iter()
+                        .filter(|snapshot| snapshot.player_id == compared.player_id)
+                        .map(|s| PlotData {
+                            x: s.frame,
+                            y: value(s),
+                        })
+                        .collect(),
+                    color: COMPARISON_COLORS[series_idx % COMPARISON_COLORS.len()],
+                    label: format!("{} ({})", player_name, export_stem(&replay.name)),
+                    style: Self::line_style(Some(compared.replay_idx) != reference_idx),
+                })
+            })
+            .collect()
+    }
+
+    /// Displays the SC2Replay general details, this is part of the Details tab.
+    fn view_details(&self, ctx: &Context<Self>, idx: usize, loaded: &LoadedReplay) -> Html {
+        let replay = &loaded.replay;
+        let on_cursor = ctx.link().callback(move |cursor| Msg::Cursor(idx, cursor));
+        let on_zoom = ctx.link().callback(move |x_range| Msg::Zoom(idx, x_range));
+        // Initially everything is aimed at just one replay.
+
+        // Sometimes the map file name exists, sometimes it's empty.
+        let mut map_name = replay.details.map_file_name.clone();
+        let map_title = replay.details.title.clone();
+        if map_name.is_empty() {
+            map_name = map_title.clone();
+        }
+        // This doesn't always works, seems like for AI games or for ESL games they have different
+        // names, maybe because official.
+        let map_link = format!(
+            "https://liquipedia.net/starcraft2/{}",
+            map_name.replace(' ', "_")
+        );
+        let map_icon_class = if replay.details.is_blizzard_map {
+            "bi-shield-plus text-success"
+        } else {
+            "bi-shield-minus text-danger"
+        };
+        let apm_series: Vec<PlotSeries> = Self::players_by_team(replay)
+            .filter_map(|(player_id, player)| {
+                let apm = replay.player_apm(player_id)?;
+                let player_name = minor_player_clan_unescape(&player.name);
+                let series = |metric: &str, value: fn(&ApmSample) -> f32, light: bool| PlotSeries {
+                    series: apm
+                        .samples
+                        .iter()
+                        .map(|sample| PlotData {
+                            x: sample.frame,
+                            y: value(sample).round() as i32,
+                        })
+                        .collect(),
+                    color: Self::player_color(player, light),
+                    label: format!("{} {}", player_name, metric),
+                    style: Self::line_style(light),
+                };
+                Some([
+                    series("APM", |s| s.apm, false),
+                    series("EPM", |s| s.epm, true),
+                ])
+            })
+            .flatten()
+            .collect();
+
+        let max_frame = replay
+            .game_snapshots
+            .iter()
+            .map(|snapshot| snapshot.frame)
+            .max()
+            .unwrap_or_default();
+        let upgrade_lanes: Vec<TimelineLane> = Self::players_by_team(replay)
+            .map(|(player_id, player)| {
+                Self::upgrade_timeline_lane(replay, player_id, Self::player_color(player, false))
+            })
+            .collect();
+
+        html! {
+            <div class={ format!("{} text-center", self.container_class()) }>
+              <div class="row">
+                <div class="col"> { &replay.name } </div>
+                <div class="col">
+                  <a href={ map_link } title={ map_title }> { map_name }</a>
+                  <i class={ map_icon_class } width="32" height="32"> </i> { &replay.details.description }
+                </div>
+                <div class="col" title={ replay.date.format_utc() }> { replay.date.format_local() } </div>
+                <div class="col" title={ format!("frame: {}", replay.last_frame) }> { replay.format_frame(replay.last_frame) } </div>
+              </div>
+              <div class="row">
+                <div class="col text-end">
+                  <div class="btn-group btn-group-sm" role="group" aria-label="Export">
+                    <button type="button" class="btn btn-outline-secondary" onclick={ctx.link().callback(move |_| Msg::Export(idx, ExportFormat::Json))}>{ "JSON" }</button>
+                    { for CsvTable::ALL.into_iter().map(|table| html! {
+                        <button type="button" class="btn btn-outline-secondary" onclick={ctx.link().callback(move |_| Msg::Export(idx, ExportFormat::Csv(table)))}>{ format!("CSV {}", table) }</button>
+                    }) }
+                  </div>
+                </div>
+              </div>
+              <div class="row">
+                <div class="col">
+                 { for replay.teams().into_iter().map(|(team_id, players)| html! {
+                     <>
+                       <div class="row"><div class="col text-start"><h4>{ format!("Team {}", team_id + 1) }</h4></div></div>
+                       { for players.into_iter().map(|(player_id, player)| html! {
+                           <>
+                             { Self::view_player_details(replay, player_id, player) }
+                             { Self::view_build_order(replay, player_id) }
+                           </>
+                       }) }
+                     </>
+                 }) }
+                </div>
+              </div>
+              <div class="row">
+              <div class="col"><h2>{ "Messages" }</h2></div>
+              </div>
+              <div class="row">
+                <div class="col">
+                 { for replay.messages.iter().map(|msg| Self::view_message_events(replay, msg)) }
+                </div>
+              </div>
+              <div class="row">
+                <Timeline lanes={upgrade_lanes} max_x={max_frame} game_speed={replay.game_speed} cursor={loaded.cursor} x_range={loaded.x_range} layout={self.layout} />
+              </div>
+              { for loaded.charts.iter().enumerate().map(|(chart_idx, chart)| self.view_snapshot_chart(ctx, idx, loaded, chart_idx, *chart)) }
+              <div class="row">
+                <div class="col text-start">
+                  <button type="button" class="btn btn-sm btn-outline-secondary" onclick={ctx.link().callback(move |_| Msg::AddChart(idx))}>{ "Add chart" }</button>
+                </div>
+              </div>
+              <div class="row">
+              <div class="col"><h2>{ "APM / EPM" }</h2></div>
+              </div>
+              <div class="row">
+                <Plot series={apm_series} export_name={format!("{} APM", export_stem(&replay.name))} y_desc="Actions per minute" game_speed={replay.game_speed} cursor={loaded.cursor} on_cursor={on_cursor.clone()} x_range={loaded.x_range} on_zoom={on_zoom.clone()} layout={self.layout} />
+              </div>
+              { self.view_minimap(ctx, idx, loaded) }
+            </div>
+        }
+    }
+
+    /// A chart of the snapshots along with the pickers of its metrics.
+    fn view_snapshot_chart(
+        &self,
+        ctx: &Context<Self>,
+        idx: usize,
+        loaded: &LoadedReplay,
+        chart_idx: usize,
+        chart: SnapshotChart,
+    ) -> Html {
+        let replay = &loaded.replay;
+        let on_cursor = ctx.link().callback(move |cursor| Msg::Cursor(idx, cursor));
+        let on_zoom = ctx.link().callback(move |x_range| Msg::Zoom(idx, x_range));
+        let series: Vec<PlotSeries> = chart
+            .secondary
+            .map(|metric| Self::metric_series(replay, metric, true))
+            .into_iter()
+            .flatten()
+            .chain(Self::metric_series(replay, chart.primary, false))
+            .collect();
+        let on_primary_change = ctx.link().batch_callback(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            SnapshotMetric::from_name(&select.value()).map(|primary| {
+                Msg::Chart(
+                    idx,
+                    chart_idx,
+                    SnapshotChart {
+                        primary,
+                        secondary: chart.secondary,
+                    },
+                )
+            })
+        });
+        let on_secondary_change = ctx.link().callback(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            Msg::Chart(
+                idx,
+                chart_idx,
+                SnapshotChart {
+                    primary: chart.primary,
+                    secondary: SnapshotMetric::from_name(&select.value()),
+                },
+            )
+        });
+        let metric_options = |selected: Option<SnapshotMetric>| {
+            html! {
+                <>
+                  { for SnapshotMetric::ALL.into_iter().map(|metric| html! {
+                      <option value={ metric.name() } selected={ selected == Some(metric) }>{ metric.label() }</option>
+                  }) }
+                </>
+            }
+        };
+        html! {
+            <>
+              <div class="row align-items-center">
+                <div class="col"><h2>{ chart.title() }</h2></div>
+                <div class="col-3">
+                  <select class="form-select form-select-sm" onchange={on_primary_change}>
+                    { metric_options(Some(chart.primary)) }
+                  </select>
+                </div>
+                <div class="col-3">
+                  <select class="form-select form-select-sm" onchange={on_secondary_change}>
+                    <option value="" selected={ chart.secondary.is_none() }>{ "No second metric" }</option>
+                    { metric_options(chart.secondary) }
+                  </select>
+                </div>
+                <div class="col-auto">
+                  <button type="button" class="btn btn-sm btn-outline-secondary" onclick={ctx.link().callback(move |_| Msg::RemoveChart(idx, chart_idx))}>{ "Remove" }</button>
+                </div>
+              </div>
+              <div class="row">
+                <Plot series={series} export_name={format!("{} {}", export_stem(&replay.name), chart.title())} y_desc={chart.title()} game_speed={replay.game_speed} cursor={loaded.cursor} on_cursor={on_cursor} x_range={loaded.x_range} on_zoom={on_zoom} layout={self.layout} />
+              </div>
+            </>
+        }
+    }
+
+    /// The units of every player at the frame chosen with the slider, structures as squares.
+    fn view_minimap(&self, ctx: &Context<Self>, idx: usize, loaded: &LoadedReplay) -> Html {
+        let replay = &loaded.replay;
+        let frame = loaded.minimap_frame;
+        let colors: HashMap<u8, RGBColor> = replay
+            .players()
+            .map(|(player_id, player)| (player_id, Self::player_color(player, false)))
+            .collect();
+        let points: Vec<MinimapPoint> = replay
+            .units
+            .alive_at(frame)
+            .filter_map(|unit| {
+                let position = unit.position_at(frame)?;
+                Some(MinimapPoint {
+                    x: position.x,
+                    y: position.y,
+                    // Mineral fields, geysers and rocks are owned by the neutral player.
+                    color: colors
+                        .get(&unit.owner)
+                        .copied()
+                        .unwrap_or(RGBColor(128, 128, 128)),
+                    is_structure: unit.is_structure(),
+                })
+            })
+            .collect();
+        let heatmap_color = loaded
+            .heatmap_player
+            .and_then(|player_id| colors.get(&player_id).copied())
+            .unwrap_or(RGBColor(255, 0, 0));
+        let heatmap: Vec<MinimapHeatCell> = match loaded.heatmap_player {
+            Some(player_id) => {
+                let counts = death_heatmap(&replay.deaths, player_id, HEATMAP_CELL_SIZE);
+                let max_count = counts.values().copied().max().unwrap_or(1);
+                counts
+                    .into_iter()
+                    .map(|((x, y), count)| MinimapHeatCell {
+                        x,
+                        y,
+                        size: HEATMAP_CELL_SIZE,
+                        intensity: f64::from(count) / f64::from(max_count),
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+        let on_heatmap_change = {
+            let player_ids: Vec<u8> = replay.players().map(|(player_id, _)| player_id).collect();
+            ctx.link().callback(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                let value = select.value();
+                Msg::HeatmapPlayer(
+                    idx,
+                    player_ids
+                        .iter()
+                        .find(|player_id| player_id.to_string() == value)
+                        .copied(),
+                )
+            })
+        };
+        let (map_width, map_height) = replay.map_size.unwrap_or_else(|| replay.units.map_extent());
+        let on_frame = ctx.link().callback(move |event: InputEvent| {
+            let input: HtmlInputElement = event.target_unchecked_into();
+            Msg::MinimapFrame(idx, input.value_as_number().max(0.) as u32)
+        });
+        html! {
+            <>
+              <div class="row">
+              <div class="col"><h2>{ "Minimap" }</h2></div>
+              </div>
+              <div class="row align-items-center">
+                <div class="col">
+                  <input type="range" class="form-range" min="0" max={replay.last_frame.to_string()} step="16" value={frame.to_string()} oninput={on_frame} />
+                </div>
+                <div class="col-auto" title={ format!("frame: {}", frame) }>{ replay.format_frame(frame) }</div>
+                <div class="col-3">
+                  <select class="form-select form-select-sm" onchange={on_heatmap_change}>
+                    <option value="" selected={ loaded.heatmap_player.is_none() }>{ "No death heatmap" }</option>
+                    { for Self::players_by_team(replay).map(|(player_id, player)| html! {
+                        <option value={ player_id.to_string() } selected={ loaded.heatmap_player == Some(player_id) }>
+                          { format!("Deaths of {}", minor_player_clan_unescape(&player.name)) }
+                        </option>
+                    }) }
+                  </select>
+                </div>
+              </div>
+              <div class="row">
+                <Minimap points={points} heatmap={heatmap} heatmap_color={heatmap_color} map_width={map_width} map_height={map_height} layout={self.layout} />
+              </div>
+              { Self::view_engagements(ctx, idx, replay) }
+            </>
+        }
+    }
+
+    /// The engagements of the replay along with the trade efficiency of each player, selecting
+    /// one moves the minimap to its start and zooms the plots in on it.
+    fn view_engagements(ctx: &Context<Self>, idx: usize, replay: &ProcessedReplay) -> Html {
+        if replay.engagements.is_empty() {
+            return html! {};
+        }
+        let players: Vec<(u8, &ReplayPlayer)> = Self::players_by_team(replay).collect();
+        // Some context before and after the deaths, the armies meet before the first one dies.
+        let margin = replay.game_speed.seconds_to_loops(15.);
+        html! {
+            <div class="row">
+              <div class="col">
+                <table class="table table-sm table-striped text-start">
+                  <thead>
+                    <tr>
+                      <th>{ "Engagement" }</th>
+                      <th>{ "Duration" }</th>
+                      <th>{ "Location" }</th>
+                      { for players.iter().map(|(_, player)| html! {
+                          <>
+                            <th>{ format!("{} lost", minor_player_clan_unescape(&player.name)) }</th>
+                            <th title="Resources killed per resource lost">{ "Trade" }</th>
+                          </>
+                      }) }
+                      <th></th>
+                    </tr>
+                  </thead>
+                  <tbody>
+                    { for replay.engagements.iter().map(|engagement| {
+                        let start = engagement.start_frame;
+                        let x_range = (start.saturating_sub(margin), (engagement.end_frame + margin).min(replay.last_frame));
+                        let on_select = ctx.link().batch_callback(move |_| {
+                            vec![Msg::MinimapFrame(idx, start), Msg::Zoom(idx, Some(x_range))]
+                        });
+                        html! {
+                          <tr>
+                            <td title={ format!("frame: {}", start) }>{ replay.format_frame(start) }</td>
+                            <td>{ replay.format_frame(engagement.end_frame - start) }</td>
+                            <td>{ format!("{}, {}", engagement.x, engagement.y) }</td>
+                            { for players.iter().map(|(player_id, _)| {
+                                let losses = engagement.losses_of(*player_id);
+                                let trade = engagement.trade(&replay.game_snapshots, *player_id);
+                                html! {
+                                  <>
+                                    <td title={ format!("{} minerals, {} vespene", losses.minerals, losses.vespene) }>
+                                      { format!("{} units, {}", losses.units, losses.resources()) }
+                                    </td>
+                                    <td title={ format!("killed {}, lost {}", trade.resources_killed, trade.resources_lost) }>
+                                      { trade.efficiency().map(|efficiency| format!("{:.2}", efficiency)).unwrap_or_else(|| "-".to_string()) }
+                                    </td>
+                                  </>
+                                }
+                            }) }
+                            <td><button type="button" class="btn btn-sm btn-outline-secondary" onclick={on_select}>{ "Show" }</button></td>
+                          </tr>
+                        }
+                    }) }
+                  </tbody>
+                </table>
+              </div>
+            </div>
+        }
+    }
+
+    /// To be called over the chat messages.
+    fn view_message_events(replay: &ProcessedReplay, msg: &ChatMessage) -> Html {
+        let source_user_name = replay.roster.name_by_user_id(msg.user_id);
+        html! {
+            <div class="row m-0 p-0">
+                <div class="col-1 m-0 p-0" title={ format!("frame: {}", msg.frame) }>{ replay.format_frame(msg.frame) }</div>
+                <div class="col-2 m-0 p-0" ><code>{ source_user_name }</code>{ ":" }</div>
+                <div class="col-1 m-0 p-0" >{ msg.user_id }  </div>
+                <div class="col-1 m-0 p-0" >{ msg.recipient.to_string() }  </div>
+                <div class="col-7 m-0 p-0 text-start" >{ &msg.text }</div>
+            </div>
+        }
+    }
+
+    /// The players ordered by team, along with their tracker events player id.
+    fn players_by_team(replay: &ProcessedReplay) -> impl Iterator<Item = (u8, &ReplayPlayer)> {
+        replay.teams().into_values().flatten()
+    }
+
+    /// The in-game color of the player, the `light` variant is used for the secondary metric of
+    /// a plot, i.e. the vespene income next to the mineral income.
+    fn player_color(player: &ReplayPlayer, light: bool) -> RGBColor {
+        let color = &player.color;
+        if light {
+            RGBColor(color.r / 2 + 128, color.g / 2 + 128, color.b / 2 + 128)
+        } else {
+            RGBColor(color.r, color.g, color.b)
+        }
+    }
+
+    /// Secondary metrics are dashed so that they can be told apart when printed in grayscale.
+    fn line_style(light: bool) -> LineStyle {
+        if light {
+            LineStyle::Dashed
+        } else {
+            LineStyle::Solid
+        }
+    }
+
+    /// Creates one series per player, grouped by team, with the metric returned by `value`.
+    /// The series are labelled with the player name and the `metric` name.
+    fn player_series(
+        replay: &ProcessedReplay,
+        metric: &str,
+        value: impl Fn(&GameSnapshot) -> i32,
+        light: bool,
+    ) -> Vec<PlotSeries> {
+        Self::players_by_team(replay)
+            .map(|(player_id, player)| PlotSeries {
+                series: replay
+                    .game_snapshots
+                    .iter()
+                    .filter(|snapshot| snapshot.player_id == player_id)
+                    .map(|s| PlotData {
+                        x: s.frame,
+                        y: value(s),
+                    })
+                    .collect(),
+                color: Self::player_color(player, light),
+                label: format!("{} {}", minor_player_clan_unescape(&player.name), metric),
+                style: Self::line_style(light),
+            })
+            .collect()
+    }
+
+    /// Creates one series per player with the `metric`, labelled with the player name and the
+    /// metric.
+    fn metric_series(
+        replay: &ProcessedReplay,
+        metric: SnapshotMetric,
+        light: bool,
+    ) -> Vec<PlotSeries> {
+        Self::player_series(
+            replay,
+            &metric.label().to_lowercase(),
+            |s| metric.value(s),
+            light,
+        )
+    }
+
+    /// Creates the timeline lane with the upgrades completed by a player, the `player_id` is the
+    /// tracker events player id.
+    fn upgrade_timeline_lane(
+        replay: &ProcessedReplay,
+        player_id: u8,
+        color: RGBColor,
+    ) -> TimelineLane {
+        TimelineLane {
+            events: replay
+                .upgrades
+                .iter()
+                .filter(|upgrade| upgrade.player_id == player_id)
+                .map(|upgrade| TimelineEvent {
+                    x: upgrade.frame,
+                    label: upgrade.name.clone(),
+                })
+                .collect(),
+            color,
+        }
+    }
+
+    /// Displays the build order of a player as a table, the `player_id` is the tracker events
+    /// player id.
+    fn view_build_order(replay: &ProcessedReplay, player_id: u8) -> Html {
+        html! {
+            <div class="row">
+              <div class="col">
+                <table class="table table-sm table-striped text-start">
+                  <thead>
+                    <tr>
+                      <th scope="col">{ "Time" }</th>
+                      <th scope="col">{ "Supply" }</th>
+                      <th scope="col">{ "Kind" }</th>
+                      <th scope="col">{ "Name" }</th>
+                    </tr>
+                  </thead>
+                  <tbody>
+                  { for replay.player_build_order(player_id).map(|entry| html! {
+                    <tr>
+                      <td title={ format!("frame: {}", entry.frame) }>{ replay.format_frame(entry.frame) }</td>
+                      <td>{ format!("{}/{}", entry.supply_used, entry.supply_available) }</td>
+                      <td>{ entry.kind.to_string() }</td>
+                      <td>{ &entry.name }</td>
+                    </tr>
+                  }) }
+                  </tbody>
+                </table>
+              </div>
+            </div>
+        }
+    }
+
+    /// Displays a file that could not be processed as an error card.
+    fn view_failed_replay(failed: &FailedReplay) -> Html {
+        html! {
+            <div class="card text-bg-danger m-1">
+              <div class="card-header"> { &failed.name } </div>
+              <div class="card-body">
+                <p class="card-text"> { failed.error.to_string() } </p>
+              </div>
+            </div>
+        }
+    }
+
+    /// To be called over the player list detail items, the `player_id` is the tracker events
+    /// player id.
+    fn view_player_details(replay: &ProcessedReplay, player_id: u8, player: &ReplayPlayer) -> Html {
+        // Create a friendly representation of who won or loss the game:
+        let alert_type = match player.result {
+            GameResult::Win => "success",
+            GameResult::Tie => "warning",
+            GameResult::Loss => "danger",
+            GameResult::Undecided => "info",
+        };
+        let player_win_classes = format!("col-1 alert alert-{} m-1 p-1", alert_type);
+        let color = format!(
+            "background:rgba({},{},{},{})",
+            player.color.r, player.color.g, player.color.b, player.color.a,
+        );
+        let player_name = minor_player_clan_unescape(&player.name);
+        let player_url = format!(
+            "https://starcraft2.blizzard.com/en-us/profile/{}/{}/{}",
+            player.toon.region, player.toon.realm, player.toon.id,
+        );
+        let apm = match replay.player_apm(player_id) {
+            Some(apm) => format!("APM {:.0} / EPM {:.0}", apm.apm, apm.epm),
+            None => "".to_string(),
+        };
+        html! {
+              <div class="row">
+                <div class="col-1" style={ color }>  </div>
+                <div class="col-1"></div>
+                <div class="col-2"> { &player.race } </div>
+                <div class="col-4"> <a href={ player_url }> { player_name } </a> </div>
+                <div class={ player_win_classes } > { player.result.to_string() } </div>
+                <div class="col-2"> { apm } </div>
+              </div>
+        }
+    }
+
+    fn upload_files(files: Option<FileList>) -> Msg {
+        let mut result = Vec::new();
+
+        if let Some(files) = files {
+            let files = (0..files.length())
+                .filter_map(|index| files.get(index))
+                .map(File::from);
+            result.extend(files);
+        }
+        Msg::Files(result)
+    }
+}
@@ -0,0 +1,354 @@
+//! Native command line interface to analyse SC2Replay files without a browser.
+//!
+//! Usage: `cooper-cli [--json] [--output <file>] [--export <directory>] <SC2Replay or directory>...`
+//!
+//! With `--export`, the full data of every replay is written to the directory as a cooper replay
+//! summary and one CSV file per table, named after the replay file. Summaries can be given back
+//! as input instead of the SC2Replay files.
+
+use cooper::export::{export_stem, to_csv};
+use cooper::summary::is_summary_file_name;
+use cooper::{
+    minor_player_clan_unescape, CsvTable, ProcessedReplay, ReplayError, ReplayIdentity,
+    ReplaySummary,
+};
+use serde_json::json;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str =
+    "Usage: cooper-cli [--json] [--output <file>] [--export <directory>] <SC2Replay or directory>...";
+
+#[derive(Debug, PartialEq, Default)]
+struct CliArgs {
+    json: bool,
+    output: Option<PathBuf>,
+    export_dir: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+}
+
+/// Parses the arguments without the program name, returns None when the usage was asked for.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<CliArgs>, String> {
+    let mut cli_args = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => cli_args.json = true,
+            "-o" | "--output" => match args.next() {
+                Some(output) => cli_args.output = Some(PathBuf::from(output)),
+                None => return Err(format!("Missing file name for {}", arg)),
+            },
+            "--export" => match args.next() {
+                Some(export_dir) => cli_args.export_dir = Some(PathBuf::from(export_dir)),
+                None => return Err(format!("Missing directory for {}", arg)),
+            },
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}\n{}", arg, USAGE)),
+            _ => cli_args.paths.push(PathBuf::from(arg)),
+        }
+    }
+    if cli_args.paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(Some(cli_args))
+}
+
+/// Directories are expanded (non-recursively) to the SC2Replay files and the cooper summaries
+/// they contain.
+fn collect_replay_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut res = vec![];
+    for path in paths {
+        if path.is_dir() {
+            let mut dir_replays: Vec<PathBuf> = match fs::read_dir(path) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|entry| is_replay_file(entry) || is_summary_file(entry))
+                    .collect(),
+                Err(err) => {
+                    eprintln!("Unable to read directory {}: {}", path.display(), err);
+                    continue;
+                }
+            };
+            dir_replays.sort();
+            res.extend(dir_replays);
+        } else {
+            res.push(path.clone());
+        }
+    }
+    res
+}
+
+fn is_replay_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("SC2Replay"))
+        .unwrap_or(false)
+}
+
+fn is_summary_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| is_summary_file_name(&name.to_string_lossy()))
+        .unwrap_or(false)
+}
+
+fn process_file(path: &Path) -> Result<ProcessedReplay, ReplayError> {
+    let data = fs::read(path).map_err(|err| ReplayError::Read(err.to_string()))?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    ProcessedReplay::from_file(&file_name, &data)
+}
+
+/// Writes `<stem>.json` and `<stem>.<table>.csv` to `export_dir`.
+fn export_replay(
+    export_dir: &Path,
+    stem: &str,
+    replay: &ProcessedReplay,
+) -> Result<(), Box<dyn Error>> {
+    let summary = ReplaySummary::new(replay);
+    let summary_json = summary.to_json()?;
+    fs::create_dir_all(export_dir)?;
+    fs::write(export_dir.join(format!("{}.json", stem)), summary_json)?;
+    for table in CsvTable::ALL {
+        fs::write(
+            export_dir.join(format!("{}.{}.csv", stem, table)),
+            to_csv(&summary, table),
+        )?;
+    }
+    Ok(())
+}
+
+/// The export stem of the replay, suffixed with a counter when a replay of another directory
+/// with the same file name was already exported. Compared ignoring case, like the file systems
+/// of Windows and macOS do.
+fn unique_export_stem(replay_name: &str, used_stems: &mut HashSet<String>) -> String {
+    let stem = export_stem(replay_name);
+    let mut unique_stem = stem.to_string();
+    let mut counter = 2;
+    while !used_stems.insert(unique_stem.to_lowercase()) {
+        unique_stem = format!("{}-{}", stem, counter);
+        counter += 1;
+    }
+    unique_stem
+}
+
+fn summary_row(path: &Path, replay: &ProcessedReplay) -> String {
+    let players: Vec<String> = replay
+        .details
+        .player_list
+        .iter()
+        .map(|player| {
+            format!(
+                "{} ({}, {})",
+                minor_player_clan_unescape(&player.name),
+                player.race,
+                player.result
+            )
+        })
+        .collect();
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        path.display(),
+        replay.details.map_name(),
+        replay.date.format_utc(),
+        replay.format_frame(replay.last_frame),
+        replay.messages.len(),
+        players.join(" vs "),
+    )
+}
+
+fn summary_json(path: &Path, replay: &ProcessedReplay) -> serde_json::Value {
+    let players: Vec<serde_json::Value> = replay
+        .details
+        .player_list
+        .iter()
+        .map(|player| {
+            json!({
+                "name": minor_player_clan_unescape(&player.name),
+                "race": player.race,
+                "result": player.result.to_string(),
+                "team_id": player.team_id,
+                "toon": {
+                    "region": player.toon.region,
+                    "realm": player.toon.realm,
+                    "id": player.toon.id,
+                },
+            })
+        })
+        .collect();
+    json!({
+        "path": path.display().to_string(),
+        "map": replay.details.map_name(),
+        "date_utc": replay.date.format_utc(),
+        "unix_seconds": replay.date.unix_seconds,
+        "local_offset_seconds": replay.date.local_offset_seconds,
+        "last_frame": replay.last_frame,
+        "duration_seconds": replay.duration_seconds(),
+        "message_count": replay.messages.len(),
+        "players": players,
+        "game_snapshots": replay.game_snapshots,
+        "engagements": replay.engagements,
+    })
+}
+
+fn main() -> ExitCode {
+    let cli_args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(cli_args)) => cli_args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut failed = false;
+    let mut rows = vec![];
+    let mut summaries = vec![];
+    // The same game can be given twice, i.e. saved by two players of a team.
+    let mut processed: Vec<(PathBuf, ReplayIdentity)> = vec![];
+    let mut export_stems = HashSet::new();
+    for path in collect_replay_paths(&cli_args.paths) {
+        match process_file(&path) {
+            Ok(replay) => {
+                if let Some((duplicate_path, _)) = processed
+                    .iter()
+                    .find(|(_, identity)| identity.is_same_game(&replay.identity))
+                {
+                    eprintln!(
+                        "{}: skipped, same game as {}",
+                        path.display(),
+                        duplicate_path.display()
+                    );
+                    continue;
+                }
+                processed.push((path.clone(), replay.identity.clone()));
+                if let Some(export_dir) = &cli_args.export_dir {
+                    let stem = unique_export_stem(&replay.name, &mut export_stems);
+                    if stem != export_stem(&replay.name) {
+                        eprintln!("{}: exported as {}", path.display(), stem);
+                    }
+                    if let Err(err) = export_replay(export_dir, &stem, &replay) {
+                        eprintln!("{}: unable to export: {}", path.display(), err);
+                        failed = true;
+                    }
+                }
+                if cli_args.json {
+                    summaries.push(summary_json(&path, &replay));
+                } else {
+                    rows.push(summary_row(&path, &replay));
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                failed = true;
+            }
+        }
+    }
+    let output = if cli_args.json {
+        serde_json::Value::Array(summaries).to_string()
+    } else {
+        let mut table = vec!["file\tmap\tdate\tduration\tmessages\tplayers".to_string()];
+        table.extend(rows);
+        table.join("\n")
+    };
+    let written = match &cli_args.output {
+        Some(output_path) => fs::write(output_path, output + "\n"),
+        None => writeln!(std::io::stdout(), "{}", output),
+    };
+    if let Err(err) = written {
+        eprintln!("Unable to write output: {}", err);
+        return ExitCode::FAILURE;
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn options_and_paths() {
+        assert_eq!(
+            parse_args(args(&[
+                "--json",
+                "-o",
+                "out.json",
+                "--export",
+                "out",
+                "a.SC2Replay",
+                "replays"
+            ])),
+            Ok(Some(CliArgs {
+                json: true,
+                output: Some(PathBuf::from("out.json")),
+                export_dir: Some(PathBuf::from("out")),
+                paths: vec![PathBuf::from("a.SC2Replay"), PathBuf::from("replays")],
+            }))
+        );
+    }
+
+    #[test]
+    fn help_stops_the_parsing() {
+        assert_eq!(parse_args(args(&["--help"])), Ok(None));
+        assert_eq!(
+            parse_args(args(&["a.SC2Replay", "-h", "--unknown"])),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn missing_option_values() {
+        assert_eq!(
+            parse_args(args(&["a.SC2Replay", "--output"])),
+            Err("Missing file name for --output".to_string())
+        );
+        assert_eq!(
+            parse_args(args(&["a.SC2Replay", "--export"])),
+            Err("Missing directory for --export".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_options_and_missing_paths() {
+        assert_eq!(
+            parse_args(args(&["--csv", "a.SC2Replay"])),
+            Err(format!("Unknown option --csv\n{}", USAGE))
+        );
+        assert_eq!(parse_args(args(&["--json"])), Err(USAGE.to_string()));
+    }
+
+    #[test]
+    fn export_stems_are_unique_ignoring_case() {
+        let mut used_stems = HashSet::new();
+        assert_eq!(
+            unique_export_stem("game.SC2Replay", &mut used_stems),
+            "game"
+        );
+        assert_eq!(
+            unique_export_stem("Game.SC2Replay", &mut used_stems),
+            "Game-2"
+        );
+        assert_eq!(
+            unique_export_stem("GAME.sc2replay", &mut used_stems),
+            "GAME-3"
+        );
+        assert_eq!(
+            unique_export_stem("other.SC2Replay", &mut used_stems),
+            "other"
+        );
+    }
+}
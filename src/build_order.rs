@@ -0,0 +1,256 @@
+//! Build order extraction from the unit registry and the researched upgrades.
+//! The tracker events do not report when a unit is queued, so units and upgrades are listed at
+//! the frame they finished, structures are listed at the frame they were started (UnitInit).
+//! Morphs are listed at the frame of their UnitTypeChange, units from larva keep the tag of the
+//! larva and only appear as morphs of it.
+
+use crate::units::{is_structure_type, IGNORED_UNIT_TYPES};
+use crate::{GameSnapshot, UnitLifecycle, UnitRegistry, UpgradeCompleted};
+use serde::{Deserialize, Serialize};
+
+/// The types a unit goes through while it morphs, whatever it changes into next is a new unit.
+const MORPH_INTERMEDIATE_TYPES: [&str; 8] = [
+    "Larva",
+    "Egg",
+    "BanelingCocoon",
+    "RavagerCocoon",
+    "LurkerMPEgg",
+    "BroodLordCocoon",
+    "OverlordCocoon",
+    "TransportOverlordCocoon",
+];
+
+/// Structures that morph in place, without an intermediate type. The other type changes are mode
+/// switches (sieging, burrowing, lifting off, ...) and are not part of the build order.
+const MORPHED_STRUCTURES: [&str; 5] = [
+    "Lair",
+    "Hive",
+    "OrbitalCommand",
+    "PlanetaryFortress",
+    "GreaterSpire",
+];
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum BuildOrderKind {
+    Structure,
+    Unit,
+    Upgrade,
+}
+
+impl std::fmt::Display for BuildOrderKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildOrderKind::Structure => write!(f, "Structure"),
+            BuildOrderKind::Unit => write!(f, "Unit"),
+            BuildOrderKind::Upgrade => write!(f, "Upgrade"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BuildOrderEntry {
+    pub frame: u32,
+    /// The tracker events player id.
+    pub player_id: u8,
+    pub kind: BuildOrderKind,
+    pub name: String,
+    /// Taken from the GameSnapshot of the player nearest to `frame`.
+    pub supply_used: i32,
+    pub supply_available: i32,
+}
+
+/// Returns the build order of every player, ordered by frame.
+pub fn extract_build_order(
+    units: &UnitRegistry,
+    upgrades: &[UpgradeCompleted],
+    game_snapshots: &[GameSnapshot],
+) -> Vec<BuildOrderEntry> {
+    let mut entries = vec![];
+    for unit in units.iter() {
+        if unit.owner == 0 {
+            continue;
+        }
+        // Skip the initial units, they are part of the map rather than the build order.
+        if unit.creation_frame != 0 {
+            if let Some(entry) = build_entry(unit, &unit.unit_type_name, unit.creation_frame) {
+                entries.push(entry);
+            }
+        }
+        let mut previous_type_name = unit.unit_type_name.as_str();
+        for morph in &unit.morphs {
+            if MORPH_INTERMEDIATE_TYPES.contains(&previous_type_name)
+                || MORPHED_STRUCTURES.contains(&morph.unit_type_name.as_str())
+            {
+                if let Some(entry) = build_entry(unit, &morph.unit_type_name, morph.frame) {
+                    entries.push(entry);
+                }
+            }
+            previous_type_name = &morph.unit_type_name;
+        }
+    }
+    for upgrade in upgrades {
+        entries.push((
+            upgrade.frame,
+            upgrade.player_id,
+            BuildOrderKind::Upgrade,
+            upgrade.name.clone(),
+        ));
+    }
+    entries.sort_by_key(|(frame, player_id, _, _)| (*frame, *player_id));
+    entries
+        .into_iter()
+        .map(|(frame, player_id, kind, name)| {
+            let (supply_used, supply_available) =
+                nearest_snapshot(game_snapshots, player_id, frame)
+                    .map(|snapshot| (snapshot.supply_used, snapshot.supply_available))
+                    .unwrap_or_default();
+            BuildOrderEntry {
+                frame,
+                player_id,
+                kind,
+                name,
+                supply_used,
+                supply_available,
+            }
+        })
+        .collect()
+}
+
+/// The entry of a unit that was created or morphed as `unit_type_name` at `frame`. Structures are
+/// listed when they were started, units when they finished.
+fn build_entry(
+    unit: &UnitLifecycle,
+    unit_type_name: &str,
+    frame: u32,
+) -> Option<(u32, u8, BuildOrderKind, String)> {
+    if IGNORED_UNIT_TYPES.contains(&unit_type_name) {
+        return None;
+    }
+    let (kind, frame) = if is_structure_type(unit_type_name) {
+        (BuildOrderKind::Structure, frame)
+    } else if frame == unit.creation_frame {
+        // Warped in units are initialized, they finish with their UnitDone.
+        let completion_frame = unit.completion_frame.unwrap_or(frame);
+        (BuildOrderKind::Unit, completion_frame)
+    } else {
+        (BuildOrderKind::Unit, frame)
+    };
+    Some((frame, unit.owner, kind, unit_type_name.to_string()))
+}
+
+/// Finds the snapshot of the player closest in time to `frame`.
+pub fn nearest_snapshot(
+    game_snapshots: &[GameSnapshot],
+    player_id: u8,
+    frame: u32,
+) -> Option<&GameSnapshot> {
+    game_snapshots
+        .iter()
+        .filter(|snapshot| snapshot.player_id == player_id)
+        .min_by_key(|snapshot| snapshot.frame.abs_diff(frame))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::UnitMorph;
+    use crate::UnitTag;
+
+    fn unit(
+        index: u32,
+        unit_type_name: &str,
+        creation_frame: u32,
+        completion_frame: u32,
+        morphs: &[(u32, &str)],
+    ) -> UnitLifecycle {
+        UnitLifecycle {
+            tag: UnitTag::new(index, 1),
+            unit_type_name: unit_type_name.to_string(),
+            owner: 1,
+            creation_frame,
+            completion_frame: Some(completion_frame),
+            death_frame: None,
+            killer_player_id: None,
+            killer_unit: None,
+            morphs: morphs
+                .iter()
+                .map(|(frame, unit_type_name)| UnitMorph {
+                    frame: *frame,
+                    unit_type_name: unit_type_name.to_string(),
+                })
+                .collect(),
+            positions: vec![],
+        }
+    }
+
+    fn entries(units: Vec<UnitLifecycle>) -> Vec<(u32, BuildOrderKind, String)> {
+        extract_build_order(&UnitRegistry::from_units(units), &[], &[])
+            .into_iter()
+            .map(|entry| (entry.frame, entry.kind, entry.name))
+            .collect()
+    }
+
+    #[test]
+    fn zerg_morphs_are_listed_at_their_frame() {
+        let units = vec![
+            unit(1, "Hatchery", 0, 0, &[(3000, "Lair"), (6000, "Hive")]),
+            unit(2, "Larva", 100, 100, &[(200, "Egg"), (500, "Zergling")]),
+            unit(
+                3,
+                "Larva",
+                150,
+                150,
+                &[
+                    (250, "Egg"),
+                    (600, "Overlord"),
+                    (900, "OverlordCocoon"),
+                    (1250, "Overseer"),
+                ],
+            ),
+            unit(
+                4,
+                "Zergling",
+                500,
+                500,
+                &[(700, "BanelingCocoon"), (1000, "Baneling")],
+            ),
+            unit(
+                5,
+                "Roach",
+                800,
+                800,
+                &[(1200, "RoachBurrowed"), (1300, "Roach")],
+            ),
+        ];
+        assert_eq!(
+            entries(units),
+            vec![
+                (500, BuildOrderKind::Unit, "Zergling".to_string()),
+                (500, BuildOrderKind::Unit, "Zergling".to_string()),
+                (600, BuildOrderKind::Unit, "Overlord".to_string()),
+                (800, BuildOrderKind::Unit, "Roach".to_string()),
+                (1000, BuildOrderKind::Unit, "Baneling".to_string()),
+                (1250, BuildOrderKind::Unit, "Overseer".to_string()),
+                (3000, BuildOrderKind::Structure, "Lair".to_string()),
+                (6000, BuildOrderKind::Structure, "Hive".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn warped_in_units_are_units() {
+        let units = vec![
+            unit(1, "Pylon", 400, 800, &[]),
+            unit(2, "Gateway", 900, 1800, &[(3000, "WarpGate")]),
+            unit(3, "Zealot", 3100, 3170, &[]),
+        ];
+        assert_eq!(
+            entries(units),
+            vec![
+                (400, BuildOrderKind::Structure, "Pylon".to_string()),
+                (900, BuildOrderKind::Structure, "Gateway".to_string()),
+                (3170, BuildOrderKind::Unit, "Zealot".to_string()),
+            ]
+        );
+    }
+}
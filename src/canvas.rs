@@ -0,0 +1,122 @@
+//! Sizing of the plot canvases from their container and the device pixel ratio.
+
+use base64::Engine;
+use gloo::events::EventListener;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlAnchorElement, HtmlCanvasElement};
+use yew::Callback;
+
+/// How much of the page width the plots take.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum PlotLayout {
+    /// Plots are at most `COMPACT_MAX_WIDTH` pixels wide.
+    #[default]
+    Compact,
+    /// Plots take the whole width of their container.
+    FullWidth,
+}
+
+/// The maximum width of the plots in the compact layout, in CSS pixels.
+const COMPACT_MAX_WIDTH: u32 = 600;
+
+impl PlotLayout {
+    /// The width and height of a plot in CSS pixels for a container of `container_width`.
+    pub fn plot_size(&self, container_width: u32) -> (u32, u32) {
+        match self {
+            PlotLayout::Compact => {
+                let width = container_width.min(COMPACT_MAX_WIDTH);
+                (width, width * 2 / 3)
+            }
+            PlotLayout::FullWidth => (container_width, (container_width * 2 / 5).clamp(300, 600)),
+        }
+    }
+}
+
+pub fn device_pixel_ratio() -> f64 {
+    gloo::utils::window().device_pixel_ratio().max(1.)
+}
+
+/// Sizes the canvas to `css_width` x `css_height` CSS pixels while backing it with as many
+/// pixels as the device has, so that it's sharp on HiDPI screens.
+/// Returns the scale that must be applied to everything drawn in pixels, i.e. fonts and margins.
+pub fn resize_canvas(canvas: &HtmlCanvasElement, css_width: u32, css_height: u32) -> f64 {
+    let scale = device_pixel_ratio();
+    canvas.set_width((css_width as f64 * scale).round() as u32);
+    canvas.set_height((css_height as f64 * scale).round() as u32);
+    let _ = canvas.set_attribute(
+        "style",
+        &format!("width:{}px;height:{}px", css_width, css_height),
+    );
+    scale
+}
+
+/// The width available to the canvas, in CSS pixels.
+pub fn container_width(container: Option<Element>) -> u32 {
+    container
+        .map(|container| container.client_width().max(0) as u32)
+        .filter(|width| *width > 0)
+        .unwrap_or(COMPACT_MAX_WIDTH)
+}
+
+/// Notifies `on_resize` when the window is resized or the device pixel ratio changes, i.e. the
+/// browser is zoomed or the window moves to a screen with a different density.
+pub struct ResizeWatcher {
+    _resize_listener: EventListener,
+    dpr_listener: Option<(f64, EventListener)>,
+    on_resize: Callback<()>,
+}
+
+impl ResizeWatcher {
+    pub fn new(on_resize: Callback<()>) -> Self {
+        let callback = on_resize.clone();
+        let resize_listener =
+            EventListener::new(&gloo::utils::window(), "resize", move |_| callback.emit(()));
+        Self {
+            _resize_listener: resize_listener,
+            dpr_listener: None,
+            on_resize,
+        }
+    }
+
+    /// The media query only matches the current device pixel ratio, so it has to be recreated
+    /// after each change, this should be called on every redraw.
+    pub fn watch_device_pixel_ratio(&mut self) {
+        let dpr = device_pixel_ratio();
+        if matches!(self.dpr_listener, Some((watched, _)) if watched == dpr) {
+            return;
+        }
+        let query = format!("(resolution: {}dppx)", dpr);
+        if let Ok(Some(media_query_list)) = gloo::utils::window().match_media(&query) {
+            let callback = self.on_resize.clone();
+            let listener =
+                EventListener::new(&media_query_list, "change", move |_| callback.emit(()));
+            self.dpr_listener = Some((dpr, listener));
+        }
+    }
+}
+
+/// Makes the browser download `data_url` as `file_name`.
+pub fn download_data_url(file_name: &str, data_url: &str) {
+    let anchor = match gloo::utils::document().create_element("a") {
+        Ok(element) => element.unchecked_into::<HtmlAnchorElement>(),
+        Err(_) => return,
+    };
+    anchor.set_href(data_url);
+    anchor.set_download(file_name);
+    anchor.click();
+}
+
+/// Makes the browser download `contents` as `file_name`.
+pub fn download_text(file_name: &str, mime_type: &str, contents: &str) {
+    download_bytes(file_name, mime_type, contents.as_bytes());
+}
+
+/// Makes the browser download `contents` as `file_name`.
+pub fn download_bytes(file_name: &str, mime_type: &str, contents: &[u8]) {
+    let data_url = format!(
+        "data:{};base64,{}",
+        mime_type,
+        base64::engine::general_purpose::STANDARD.encode(contents)
+    );
+    download_data_url(file_name, &data_url);
+}
@@ -0,0 +1,124 @@
+//! The parts of the s2protocol `Details` cooper uses, copied into owned types so that a
+//! `ProcessedReplay` can be serialised and rebuilt from a `ReplaySummary` without the replay file.
+
+use s2protocol::details::{Details, PlayerDetails, ResultDetails};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum GameResult {
+    Win,
+    Loss,
+    Tie,
+    Undecided,
+}
+
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::Win => write!(f, "Winner"),
+            GameResult::Loss => write!(f, "Lost"),
+            GameResult::Tie => write!(f, "Tie"),
+            GameResult::Undecided => write!(f, "Undecided"),
+        }
+    }
+}
+
+impl From<&ResultDetails> for GameResult {
+    fn from(result: &ResultDetails) -> Self {
+        match result {
+            ResultDetails::EWin => GameResult::Win,
+            ResultDetails::ELoss => GameResult::Loss,
+            ResultDetails::ETie => GameResult::Tie,
+            ResultDetails::EUndecided => GameResult::Undecided,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub struct PlayerColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// The battle.net account of a player.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize)]
+pub struct Toon {
+    pub region: u8,
+    pub realm: u32,
+    pub id: u64,
+}
+
+impl std::fmt::Display for Toon {
+    /// The handle as shown in the profile URLs, `<region>-S2-<realm>-<id>`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-S2-{}-{}", self.region, self.realm, self.id)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReplayPlayer {
+    /// The name as stored in the replay, the clan tag is HTML escaped.
+    pub name: String,
+    pub race: String,
+    pub result: GameResult,
+    pub team_id: u8,
+    pub color: PlayerColor,
+    pub toon: Toon,
+}
+
+impl From<&PlayerDetails> for ReplayPlayer {
+    fn from(player: &PlayerDetails) -> Self {
+        Self {
+            name: player.name.clone(),
+            race: player.race.clone(),
+            result: GameResult::from(&player.result),
+            team_id: player.team_id,
+            color: PlayerColor {
+                r: player.color.r,
+                g: player.color.g,
+                b: player.color.b,
+                a: player.color.a,
+            },
+            toon: Toon {
+                region: player.toon.region,
+                realm: player.toon.realm as u32,
+                id: player.toon.id as u64,
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReplayDetails {
+    pub title: String,
+    pub map_file_name: String,
+    pub description: String,
+    pub is_blizzard_map: bool,
+    pub player_list: Vec<ReplayPlayer>,
+}
+
+impl ReplayDetails {
+    /// The map file name when available, the map title otherwise.
+    pub fn map_name(&self) -> &str {
+        // Sometimes the map file name exists, sometimes it's empty.
+        if self.map_file_name.is_empty() {
+            &self.title
+        } else {
+            &self.map_file_name
+        }
+    }
+}
+
+impl From<&Details> for ReplayDetails {
+    fn from(details: &Details) -> Self {
+        Self {
+            title: details.title.clone(),
+            map_file_name: details.map_file_name.clone(),
+            description: details.description.clone(),
+            is_blizzard_map: details.is_blizzard_map,
+            player_list: details.player_list.iter().map(ReplayPlayer::from).collect(),
+        }
+    }
+}
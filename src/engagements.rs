@@ -0,0 +1,501 @@
+//! Where units died and the fights they died in. Deaths close in time and space are grouped
+//! into engagements, with the resources each player lost in them.
+//!
+//! The tracker events do not carry the cost of the units, it is looked up by unit type in
+//! `UNIT_COSTS`, unknown unit types are counted without a cost.
+
+use crate::units::IGNORED_UNIT_TYPES;
+use crate::{GameSnapshot, GameSpeed, UnitLifecycle, UnitRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Deaths further apart than this, in in-game seconds, are not part of the same engagement.
+pub const ENGAGEMENT_GAP_SECONDS: f32 = 10.;
+
+/// Deaths further than this, in map cells, from the center of an engagement start another one.
+pub const ENGAGEMENT_RADIUS: u32 = 16;
+
+/// Engagements with fewer deaths are skirmishes, i.e. a scouting worker being killed.
+pub const MIN_ENGAGEMENT_DEATHS: usize = 4;
+
+/// The minerals and vespene a unit type costs, morphs include the cost of the unit they morph
+/// from, i.e. a Baneling includes its Zergling.
+const UNIT_COSTS: [(&str, u32, u32); 135] = [
+    // Protoss units.
+    ("Probe", 50, 0),
+    ("Zealot", 100, 0),
+    ("Stalker", 125, 50),
+    ("Sentry", 50, 100),
+    ("Adept", 100, 25),
+    ("HighTemplar", 50, 150),
+    ("DarkTemplar", 125, 125),
+    ("Archon", 100, 300),
+    ("Immortal", 275, 100),
+    ("Colossus", 300, 200),
+    ("Disruptor", 150, 150),
+    ("Observer", 25, 75),
+    ("ObserverSiegeMode", 25, 75),
+    ("WarpPrism", 250, 0),
+    ("WarpPrismPhasing", 250, 0),
+    ("Phoenix", 150, 100),
+    ("VoidRay", 250, 150),
+    ("Oracle", 150, 150),
+    ("Tempest", 250, 175),
+    ("Carrier", 350, 250),
+    ("Mothership", 400, 400),
+    // Protoss structures.
+    ("Nexus", 400, 0),
+    ("Pylon", 100, 0),
+    ("Assimilator", 75, 0),
+    ("Gateway", 150, 0),
+    ("WarpGate", 150, 0),
+    ("Forge", 150, 0),
+    ("CyberneticsCore", 150, 0),
+    ("PhotonCannon", 150, 0),
+    ("ShieldBattery", 100, 0),
+    ("TwilightCouncil", 150, 100),
+    ("RoboticsFacility", 150, 100),
+    ("Stargate", 150, 150),
+    ("TemplarArchive", 150, 200),
+    ("DarkShrine", 150, 150),
+    ("RoboticsBay", 150, 150),
+    ("FleetBeacon", 300, 200),
+    // Terran units.
+    ("SCV", 50, 0),
+    ("Marine", 50, 0),
+    ("Marauder", 100, 25),
+    ("Reaper", 50, 50),
+    ("Ghost", 150, 125),
+    ("Hellion", 100, 0),
+    ("HellionTank", 100, 0),
+    ("WidowMine", 75, 25),
+    ("WidowMineBurrowed", 75, 25),
+    ("SiegeTank", 150, 125),
+    ("SiegeTankSieged", 150, 125),
+    ("Cyclone", 125, 50),
+    ("Thor", 300, 200),
+    ("ThorAP", 300, 200),
+    ("VikingFighter", 150, 75),
+    ("VikingAssault", 150, 75),
+    ("Medivac", 100, 100),
+    ("Liberator", 150, 125),
+    ("LiberatorAG", 150, 125),
+    ("Raven", 100, 150),
+    ("Banshee", 150, 100),
+    ("Battlecruiser", 400, 300),
+    // Terran structures.
+    ("CommandCenter", 400, 0),
+    ("CommandCenterFlying", 400, 0),
+    ("OrbitalCommand", 550, 0),
+    ("OrbitalCommandFlying", 550, 0),
+    ("PlanetaryFortress", 550, 150),
+    ("SupplyDepot", 100, 0),
+    ("SupplyDepotLowered", 100, 0),
+    ("Refinery", 75, 0),
+    ("Barracks", 150, 0),
+    ("BarracksFlying", 150, 0),
+    ("BarracksReactor", 50, 50),
+    ("BarracksTechLab", 50, 25),
+    ("Factory", 150, 100),
+    ("FactoryFlying", 150, 100),
+    ("FactoryReactor", 50, 50),
+    ("FactoryTechLab", 50, 25),
+    ("Starport", 150, 100),
+    ("StarportFlying", 150, 100),
+    ("StarportReactor", 50, 50),
+    ("StarportTechLab", 50, 25),
+    ("EngineeringBay", 125, 0),
+    ("Bunker", 100, 0),
+    ("MissileTurret", 100, 0),
+    ("SensorTower", 125, 50),
+    ("Armory", 150, 50),
+    ("GhostAcademy", 150, 50),
+    ("FusionCore", 150, 150),
+    // Zerg units.
+    ("Drone", 50, 0),
+    ("DroneBurrowed", 50, 0),
+    ("Overlord", 100, 0),
+    ("OverlordTransport", 125, 25),
+    ("Overseer", 150, 50),
+    ("OverseerSiegeMode", 150, 50),
+    ("Queen", 150, 0),
+    ("QueenBurrowed", 150, 0),
+    ("Zergling", 25, 0),
+    ("ZerglingBurrowed", 25, 0),
+    ("Baneling", 50, 25),
+    ("BanelingBurrowed", 50, 25),
+    ("Roach", 75, 25),
+    ("RoachBurrowed", 75, 25),
+    ("Ravager", 100, 100),
+    ("Hydralisk", 100, 50),
+    ("HydraliskBurrowed", 100, 50),
+    ("LurkerMP", 150, 150),
+    ("LurkerMPBurrowed", 150, 150),
+    ("Infestor", 100, 150),
+    ("InfestorBurrowed", 100, 150),
+    ("SwarmHostMP", 100, 75),
+    ("SwarmHostBurrowedMP", 100, 75),
+    ("Ultralisk", 275, 200),
+    ("UltraliskBurrowed", 275, 200),
+    ("Mutalisk", 100, 100),
+    ("Corruptor", 150, 100),
+    ("BroodLord", 300, 250),
+    ("Viper", 100, 200),
+    // Zerg structures, without the Drone they cost.
+    ("Hatchery", 300, 0),
+    ("Lair", 450, 100),
+    ("Hive", 650, 250),
+    ("Extractor", 25, 0),
+    ("SpawningPool", 200, 0),
+    ("EvolutionChamber", 75, 0),
+    ("RoachWarren", 150, 0),
+    ("BanelingNest", 100, 50),
+    ("SpineCrawler", 100, 0),
+    ("SpineCrawlerUprooted", 100, 0),
+    ("SporeCrawler", 75, 0),
+    ("SporeCrawlerUprooted", 75, 0),
+    ("HydraliskDen", 100, 100),
+    ("LurkerDenMP", 100, 150),
+    ("InfestationPit", 100, 100),
+    ("Spire", 200, 200),
+    ("GreaterSpire", 300, 350),
+    ("NydusNetwork", 150, 150),
+    ("NydusCanal", 75, 75),
+    ("UltraliskCavern", 150, 200),
+];
+
+/// The cost of a unit type, see `UNIT_COSTS`.
+pub fn unit_cost(unit_type_name: &str) -> Option<(u32, u32)> {
+    UNIT_COSTS
+        .iter()
+        .find(|(name, _, _)| *name == unit_type_name)
+        .map(|(_, minerals, vespene)| (*minerals, *vespene))
+}
+
+/// A unit of a player that died, at the position it died at.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UnitDeath {
+    pub frame: u32,
+    /// The tracker events player id of the owner of the unit.
+    pub player_id: u8,
+    pub killer_player_id: Option<u8>,
+    /// The type the unit had when it died.
+    pub unit_type_name: String,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl UnitDeath {
+    fn new(unit: &UnitLifecycle) -> Option<Self> {
+        let frame = unit.death_frame?;
+        if unit.owner == 0 || IGNORED_UNIT_TYPES.contains(&unit.current_type_name()) {
+            return None;
+        }
+        let position = unit.position_at(frame)?;
+        Some(Self {
+            frame,
+            player_id: unit.owner,
+            killer_player_id: unit.killer_player_id,
+            unit_type_name: unit.current_type_name().to_string(),
+            x: position.x,
+            y: position.y,
+        })
+    }
+
+    /// The minerals and vespene the unit cost, zero for unknown unit types.
+    pub fn cost(&self) -> (u32, u32) {
+        unit_cost(&self.unit_type_name).unwrap_or_default()
+    }
+}
+
+/// The deaths of the players units, neutral units and units dying on a timer excluded, ordered
+/// by frame.
+pub fn unit_deaths(units: &UnitRegistry) -> Vec<UnitDeath> {
+    let mut deaths: Vec<UnitDeath> = units.iter().filter_map(UnitDeath::new).collect();
+    deaths.sort_by_key(|death| (death.frame, death.player_id));
+    deaths
+}
+
+/// The deaths of the units of a player, counted per square of `cell_size` map cells and keyed
+/// by the bottom left corner of the square.
+pub fn death_heatmap(
+    deaths: &[UnitDeath],
+    player_id: u8,
+    cell_size: u32,
+) -> BTreeMap<(u32, u32), u32> {
+    let cell_size = cell_size.max(1);
+    let mut heatmap = BTreeMap::new();
+    for death in deaths.iter().filter(|death| death.player_id == player_id) {
+        let cell = (
+            death.x / cell_size * cell_size,
+            death.y / cell_size * cell_size,
+        );
+        *heatmap.entry(cell).or_default() += 1;
+    }
+    heatmap
+}
+
+/// What a player lost in an engagement.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct EngagementLosses {
+    pub units: u32,
+    pub minerals: u32,
+    pub vespene: u32,
+}
+
+impl EngagementLosses {
+    pub fn resources(&self) -> u32 {
+        self.minerals + self.vespene
+    }
+}
+
+/// Deaths grouped in time and space, i.e. a fight between armies or a drop in a mineral line.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Engagement {
+    /// The frame of the first death.
+    pub start_frame: u32,
+    /// The frame of the last death.
+    pub end_frame: u32,
+    /// The center of the deaths, in map cells.
+    pub x: u32,
+    pub y: u32,
+    /// Keyed by the tracker events player id, players that lost nothing are not listed.
+    pub losses: BTreeMap<u8, EngagementLosses>,
+}
+
+impl Engagement {
+    pub fn deaths(&self) -> u32 {
+        self.losses.values().map(|losses| losses.units).sum()
+    }
+
+    /// The losses of a player, zero if they lost nothing.
+    pub fn losses_of(&self, player_id: u8) -> EngagementLosses {
+        self.losses.get(&player_id).copied().unwrap_or_default()
+    }
+
+    /// The resources the player lost and killed from the last snapshot before the engagement to
+    /// the first one after it. The PlayerStats events are only emitted every 160 game loops, so
+    /// losses elsewhere on the map around the same time are counted as well.
+    pub fn trade(&self, game_snapshots: &[GameSnapshot], player_id: u8) -> EngagementTrade {
+        let snapshots = game_snapshots
+            .iter()
+            .filter(|snapshot| snapshot.player_id == player_id);
+        let before = snapshots
+            .clone()
+            .filter(|snapshot| snapshot.frame < self.start_frame)
+            .last();
+        let after = snapshots
+            .clone()
+            .find(|snapshot| snapshot.frame >= self.end_frame)
+            .or_else(|| snapshots.last());
+        let delta =
+            |value: fn(&GameSnapshot) -> i32| after.map_or(0, value) - before.map_or(0, value);
+        EngagementTrade {
+            resources_lost: delta(GameSnapshot::resources_lost),
+            resources_killed: delta(GameSnapshot::resources_killed),
+        }
+    }
+}
+
+/// What a player lost and killed during an engagement according to the PlayerStats events,
+/// unlike `EngagementLosses` this does not depend on the unit costs table.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct EngagementTrade {
+    pub resources_lost: i32,
+    pub resources_killed: i32,
+}
+
+impl EngagementTrade {
+    /// The resources killed per resource lost, None when nothing was lost.
+    pub fn efficiency(&self) -> Option<f32> {
+        if self.resources_lost > 0 {
+            Some(self.resources_killed as f32 / self.resources_lost as f32)
+        } else {
+            None
+        }
+    }
+}
+
+/// An engagement being built, the center is the mean of the death positions so far.
+struct Cluster {
+    start_frame: u32,
+    end_frame: u32,
+    sum_x: u64,
+    sum_y: u64,
+    losses: BTreeMap<u8, EngagementLosses>,
+}
+
+impl Cluster {
+    fn new(death: &UnitDeath) -> Self {
+        let mut cluster = Self {
+            start_frame: death.frame,
+            end_frame: death.frame,
+            sum_x: 0,
+            sum_y: 0,
+            losses: BTreeMap::new(),
+        };
+        cluster.add(death);
+        cluster
+    }
+
+    fn deaths(&self) -> u32 {
+        self.losses.values().map(|losses| losses.units).sum()
+    }
+
+    fn center(&self) -> (u32, u32) {
+        let deaths = u64::from(self.deaths().max(1));
+        ((self.sum_x / deaths) as u32, (self.sum_y / deaths) as u32)
+    }
+
+    fn add(&mut self, death: &UnitDeath) {
+        let (minerals, vespene) = death.cost();
+        let losses = self.losses.entry(death.player_id).or_default();
+        losses.units += 1;
+        losses.minerals += minerals;
+        losses.vespene += vespene;
+        self.end_frame = death.frame;
+        self.sum_x += u64::from(death.x);
+        self.sum_y += u64::from(death.y);
+    }
+
+    fn is_near(&self, death: &UnitDeath) -> bool {
+        let (x, y) = self.center();
+        let dx = x.abs_diff(death.x);
+        let dy = y.abs_diff(death.y);
+        dx * dx + dy * dy <= ENGAGEMENT_RADIUS * ENGAGEMENT_RADIUS
+    }
+
+    fn into_engagement(self) -> Engagement {
+        let (x, y) = self.center();
+        Engagement {
+            start_frame: self.start_frame,
+            end_frame: self.end_frame,
+            x,
+            y,
+            losses: self.losses,
+        }
+    }
+}
+
+/// Groups the deaths, ordered by frame, into engagements. A death joins the nearest engagement
+/// that had a death in the last `ENGAGEMENT_GAP_SECONDS` within `ENGAGEMENT_RADIUS`, or starts a
+/// new one, so that simultaneous fights in different places are kept apart.
+pub fn find_engagements(deaths: &[UnitDeath], game_speed: GameSpeed) -> Vec<Engagement> {
+    let gap = game_speed.seconds_to_loops(ENGAGEMENT_GAP_SECONDS);
+    let mut open: Vec<Cluster> = vec![];
+    let mut closed: Vec<Cluster> = vec![];
+    for death in deaths {
+        let (stale, active): (Vec<Cluster>, Vec<Cluster>) = open
+            .into_iter()
+            .partition(|cluster| cluster.end_frame + gap < death.frame);
+        closed.extend(stale);
+        open = active;
+        let nearest = open
+            .iter_mut()
+            .filter(|cluster| cluster.is_near(death))
+            .min_by_key(|cluster| {
+                let (x, y) = cluster.center();
+                x.abs_diff(death.x).pow(2) + y.abs_diff(death.y).pow(2)
+            });
+        match nearest {
+            Some(cluster) => cluster.add(death),
+            None => open.push(Cluster::new(death)),
+        }
+    }
+    closed.extend(open);
+    let mut engagements: Vec<Engagement> = closed
+        .into_iter()
+        .filter(|cluster| cluster.deaths() as usize >= MIN_ENGAGEMENT_DEATHS)
+        .map(Cluster::into_engagement)
+        .collect();
+    engagements.sort_by_key(|engagement| engagement.start_frame);
+    engagements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn death(frame: u32, player_id: u8, x: u32, y: u32) -> UnitDeath {
+        UnitDeath {
+            frame,
+            player_id,
+            killer_player_id: Some(3 - player_id),
+            unit_type_name: "Marine".to_string(),
+            x,
+            y,
+        }
+    }
+
+    /// Four deaths at the same place, starting at `frame`, 10 loops apart.
+    fn fight(frame: u32, x: u32, y: u32) -> Vec<UnitDeath> {
+        (0..4)
+            .map(|i| death(frame + i * 10, 1 + (i % 2) as u8, x, y))
+            .collect()
+    }
+
+    #[test]
+    fn exactly_min_deaths_make_an_engagement() {
+        // 16 loops per second at Normal speed, 160 loops of gap.
+        let deaths = fight(100, 50, 50);
+        let engagements = find_engagements(&deaths, GameSpeed::Normal);
+        assert_eq!(engagements.len(), 1);
+        assert_eq!(engagements[0].start_frame, 100);
+        assert_eq!(engagements[0].end_frame, 130);
+        assert_eq!((engagements[0].x, engagements[0].y), (50, 50));
+        assert_eq!(engagements[0].deaths(), 4);
+        assert_eq!(
+            engagements[0].losses_of(1),
+            EngagementLosses {
+                units: 2,
+                minerals: 100,
+                vespene: 0,
+            }
+        );
+        assert!(find_engagements(&deaths[..3], GameSpeed::Normal).is_empty());
+    }
+
+    #[test]
+    fn a_gap_over_the_limit_splits_engagements() {
+        let mut deaths = fight(100, 50, 50);
+        deaths.extend(fight(130 + 161, 50, 50));
+        let engagements = find_engagements(&deaths, GameSpeed::Normal);
+        assert_eq!(engagements.len(), 2);
+        assert_eq!(engagements[1].start_frame, 291);
+
+        let mut deaths = fight(100, 50, 50);
+        deaths.extend(fight(130 + 160, 50, 50));
+        let engagements = find_engagements(&deaths, GameSpeed::Normal);
+        assert_eq!(engagements.len(), 1);
+        assert_eq!(engagements[0].deaths(), 8);
+    }
+
+    #[test]
+    fn a_death_outside_the_radius_is_not_part_of_the_engagement() {
+        let mut deaths = fight(100, 50, 50);
+        deaths.push(death(140, 1, 50 + ENGAGEMENT_RADIUS + 1, 50));
+        let engagements = find_engagements(&deaths, GameSpeed::Normal);
+        assert_eq!(engagements.len(), 1);
+        assert_eq!(engagements[0].deaths(), 4);
+
+        let mut deaths = fight(100, 50, 50);
+        deaths.push(death(140, 1, 50 + ENGAGEMENT_RADIUS, 50));
+        let engagements = find_engagements(&deaths, GameSpeed::Normal);
+        assert_eq!(engagements[0].deaths(), 5);
+    }
+
+    #[test]
+    fn death_heatmap_counts_the_deaths_of_the_player_per_cell() {
+        let deaths = vec![
+            death(100, 1, 0, 0),
+            death(110, 1, 3, 3),
+            death(120, 1, 4, 7),
+            death(130, 2, 0, 0),
+        ];
+        let heatmap = death_heatmap(&deaths, 1, 4);
+        assert_eq!(
+            heatmap.into_iter().collect::<Vec<_>>(),
+            vec![((0, 0), 2), ((4, 4), 1)]
+        );
+    }
+}
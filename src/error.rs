@@ -0,0 +1,50 @@
+//! Errors surfaced while turning an SC2Replay file into a [`crate::ProcessedReplay`].
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// The file contents could not be read, i.e. from the browser File API or the filesystem.
+    Read(String),
+    /// The MPQ archive that wraps the replay could not be parsed.
+    MpqParse(String),
+    /// The protocol header stored in the MPQ user data could not be decoded.
+    ProtocolHeader(String),
+    /// The replay was saved by a game version that s2protocol cannot decode, contains the base build.
+    UnsupportedProtocol(u32),
+    /// The replay.details file contains no players.
+    MissingDetails,
+    /// The replay.tracker.events stream is empty, this happens on very old replays or on games
+    /// that ended before the first tracker event was emitted.
+    EmptyTrackerEvents,
+    /// A cooper replay summary could not be serialised or deserialised.
+    Summary(String),
+    /// The replay summary was written with a schema version this version of cooper cannot read.
+    UnsupportedSummaryVersion(u32),
+    /// The browser replay library could not be read or written.
+    Library(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Read(err) => write!(f, "Unable to read file: {}", err),
+            ReplayError::MpqParse(err) => write!(f, "Unable to parse SC2Replay: {}", err),
+            ReplayError::ProtocolHeader(err) => {
+                write!(f, "Unable to read the protocol header: {}", err)
+            }
+            ReplayError::UnsupportedProtocol(base_build) => {
+                write!(f, "Unsupported protocol version: {}", base_build)
+            }
+            ReplayError::MissingDetails => write!(f, "The replay details contain no players"),
+            ReplayError::EmptyTrackerEvents => write!(f, "The replay contains no tracker events"),
+            ReplayError::Summary(err) => write!(f, "Invalid replay summary: {}", err),
+            ReplayError::UnsupportedSummaryVersion(version) => {
+                write!(f, "Unsupported replay summary schema version: {}", version)
+            }
+            ReplayError::Library(err) => write!(f, "Replay library error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
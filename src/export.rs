@@ -0,0 +1,273 @@
+//! Flattening of a `ReplaySummary` into one CSV table per kind of data, for analysis outside of
+//! cooper. Used by the web `App` downloads and by `cooper-cli`, along with the JSON summary.
+
+use crate::{minor_player_clan_unescape, ReplaySummary, SnapshotMetric};
+
+/// The columns of the game snapshots table after the frame and the player id, every
+/// `SnapshotMetric` that is not derived. New columns are appended so that the scripts reading
+/// the table by position keep working.
+const SNAPSHOT_COLUMNS: [SnapshotMetric; 39] = [
+    SnapshotMetric::Minerals,
+    SnapshotMetric::Vespene,
+    SnapshotMetric::MineralIncome,
+    SnapshotMetric::VespeneIncome,
+    SnapshotMetric::SupplyAvailable,
+    SnapshotMetric::SupplyUsed,
+    SnapshotMetric::SupplyWorkers,
+    SnapshotMetric::ActiveForceMinerals,
+    SnapshotMetric::ActiveForceVespene,
+    SnapshotMetric::MineralsLostArmy,
+    SnapshotMetric::MineralsLostEconomy,
+    SnapshotMetric::MineralsLostTechnology,
+    SnapshotMetric::VespeneLostArmy,
+    SnapshotMetric::VespeneLostEconomy,
+    SnapshotMetric::VespeneLostTechnology,
+    SnapshotMetric::MineralsKilledArmy,
+    SnapshotMetric::MineralsKilledEconomy,
+    SnapshotMetric::MineralsKilledTechnology,
+    SnapshotMetric::VespeneKilledArmy,
+    SnapshotMetric::VespeneKilledEconomy,
+    SnapshotMetric::VespeneKilledTechnology,
+    SnapshotMetric::MineralsUsedInProgressArmy,
+    SnapshotMetric::MineralsUsedInProgressEconomy,
+    SnapshotMetric::MineralsUsedInProgressTechnology,
+    SnapshotMetric::VespeneUsedInProgressArmy,
+    SnapshotMetric::VespeneUsedInProgressEconomy,
+    SnapshotMetric::VespeneUsedInProgressTechnology,
+    SnapshotMetric::MineralsUsedCurrentArmy,
+    SnapshotMetric::MineralsUsedCurrentEconomy,
+    SnapshotMetric::MineralsUsedCurrentTechnology,
+    SnapshotMetric::VespeneUsedCurrentArmy,
+    SnapshotMetric::VespeneUsedCurrentEconomy,
+    SnapshotMetric::VespeneUsedCurrentTechnology,
+    SnapshotMetric::MineralsFriendlyFireArmy,
+    SnapshotMetric::MineralsFriendlyFireEconomy,
+    SnapshotMetric::MineralsFriendlyFireTechnology,
+    SnapshotMetric::VespeneFriendlyFireArmy,
+    SnapshotMetric::VespeneFriendlyFireEconomy,
+    SnapshotMetric::VespeneFriendlyFireTechnology,
+];
+
+/// The tables a `ReplaySummary` can be flattened into.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CsvTable {
+    Players,
+    Messages,
+    GameSnapshots,
+    Units,
+    BuildOrder,
+}
+
+impl CsvTable {
+    pub const ALL: [CsvTable; 5] = [
+        CsvTable::Players,
+        CsvTable::Messages,
+        CsvTable::GameSnapshots,
+        CsvTable::Units,
+        CsvTable::BuildOrder,
+    ];
+
+    /// The name of the table, used as the suffix of the exported files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvTable::Players => "players",
+            CsvTable::Messages => "messages",
+            CsvTable::GameSnapshots => "snapshots",
+            CsvTable::Units => "units",
+            CsvTable::BuildOrder => "build_order",
+        }
+    }
+}
+
+impl std::fmt::Display for CsvTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Flattens one kind of data of the summary into CSV, with a header row.
+pub fn to_csv(summary: &ReplaySummary, table: CsvTable) -> String {
+    let mut rows: Vec<Vec<String>> = vec![];
+    match table {
+        CsvTable::Players => {
+            rows.push(csv_header(&[
+                "player_id",
+                "user_id",
+                "name",
+                "race",
+                "result",
+                "team_id",
+                "toon",
+                "color",
+                "apm",
+                "epm",
+            ]));
+            for player in &summary.roster {
+                let apm = summary
+                    .time_series
+                    .apm
+                    .iter()
+                    .find(|apm| Some(apm.user_id) == player.user_id);
+                let color = &player.details.color;
+                rows.push(vec![
+                    player.player_id.to_string(),
+                    csv_option(player.user_id),
+                    minor_player_clan_unescape(&player.details.name),
+                    player.details.race.clone(),
+                    player.details.result.to_string(),
+                    player.details.team_id.to_string(),
+                    player.details.toon.to_string(),
+                    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b),
+                    csv_option(apm.map(|apm| apm.apm)),
+                    csv_option(apm.map(|apm| apm.epm)),
+                ]);
+            }
+        }
+        CsvTable::Messages => {
+            rows.push(csv_header(&[
+                "frame",
+                "user_id",
+                "sender",
+                "recipient",
+                "text",
+            ]));
+            for msg in &summary.events.messages {
+                let player = summary
+                    .roster
+                    .iter()
+                    .find(|player| player.user_id == Some(msg.user_id));
+                let observer = summary
+                    .observers
+                    .iter()
+                    .find(|observer| observer.user_id == msg.user_id);
+                let sender = match (player, observer) {
+                    (Some(player), _) => minor_player_clan_unescape(&player.details.name),
+                    (None, Some(observer)) => observer.name.clone(),
+                    (None, None) => format!("Observer {}", msg.user_id),
+                };
+                rows.push(vec![
+                    msg.frame.to_string(),
+                    msg.user_id.to_string(),
+                    sender,
+                    msg.recipient.to_string(),
+                    msg.text.clone(),
+                ]);
+            }
+        }
+        CsvTable::GameSnapshots => {
+            let metrics = SNAPSHOT_COLUMNS.into_iter();
+            let header: Vec<&str> = ["frame", "player_id"]
+                .into_iter()
+                .chain(metrics.clone().map(|metric| metric.name()))
+                .collect();
+            rows.push(csv_header(&header));
+            for snapshot in &summary.time_series.game_snapshots {
+                rows.push(
+                    [snapshot.frame.to_string(), snapshot.player_id.to_string()]
+                        .into_iter()
+                        .chain(
+                            metrics
+                                .clone()
+                                .map(|metric| metric.value(snapshot).to_string()),
+                        )
+                        .collect(),
+                );
+            }
+        }
+        CsvTable::Units => {
+            rows.push(csv_header(&[
+                "index",
+                "recycle",
+                "owner",
+                "unit_type_name",
+                "current_type_name",
+                "creation_frame",
+                "completion_frame",
+                "death_frame",
+                "killer_player_id",
+            ]));
+            for unit in &summary.events.units {
+                rows.push(vec![
+                    unit.tag.index.to_string(),
+                    unit.tag.recycle.to_string(),
+                    unit.owner.to_string(),
+                    unit.unit_type_name.clone(),
+                    unit.current_type_name().to_string(),
+                    unit.creation_frame.to_string(),
+                    csv_option(unit.completion_frame),
+                    csv_option(unit.death_frame),
+                    csv_option(unit.killer_player_id),
+                ]);
+            }
+        }
+        CsvTable::BuildOrder => {
+            rows.push(csv_header(&[
+                "frame",
+                "player_id",
+                "kind",
+                "name",
+                "supply_used",
+                "supply_available",
+            ]));
+            for entry in &summary.events.build_order {
+                rows.push(vec![
+                    entry.frame.to_string(),
+                    entry.player_id.to_string(),
+                    entry.kind.to_string(),
+                    entry.name.clone(),
+                    entry.supply_used.to_string(),
+                    entry.supply_available.to_string(),
+                ]);
+            }
+        }
+    }
+    let mut res = String::new();
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| csv_escape(field)).collect();
+        res.push_str(&fields.join(","));
+        res.push('\n');
+    }
+    res
+}
+
+/// The file name of the replay without the `.SC2Replay` extension, to name the exported files.
+pub fn export_stem(replay_name: &str) -> &str {
+    match replay_name.rsplit_once('.') {
+        Some((stem, ext)) if ext.eq_ignore_ascii_case("SC2Replay") => stem,
+        _ => replay_name,
+    }
+}
+
+fn csv_header(columns: &[&str]) -> Vec<String> {
+    columns.iter().map(|column| column.to_string()).collect()
+}
+
+fn csv_option<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Quotes fields containing separators, quotes or line breaks, chat messages often do.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_columns_are_the_stored_metrics() {
+        let stored: Vec<SnapshotMetric> = SnapshotMetric::ALL
+            .into_iter()
+            .filter(|metric| !metric.is_derived())
+            .collect();
+        assert_eq!(SNAPSHOT_COLUMNS.len(), stored.len());
+        assert!(stored
+            .iter()
+            .all(|metric| SNAPSHOT_COLUMNS.contains(metric)));
+    }
+}
@@ -0,0 +1,159 @@
+//! Identification of replays, to detect the same file being loaded twice and the same game saved
+//! by different players.
+//!
+//! Every copy of a game shares the random seed of the init data and the accounts that played it.
+//! The save date and the map title are not used, each client saves the replay when it leaves the
+//! game and the map title is localized in the language of the client.
+
+use crate::{ReplayDate, ReplayDetails};
+use serde::{Deserialize, Serialize};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Copies of a game without a known random seed are the same game if their estimated start
+/// times are this close, in seconds.
+pub const GAME_START_TOLERANCE_SECONDS: i64 = 30;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ReplayIdentity {
+    /// The hash of the SC2Replay file, as hex. Unknown for summaries written before it existed.
+    pub content_hash: Option<String>,
+    /// The seed of the game from the init data. Unknown for summaries written before it existed.
+    pub random_seed: Option<u32>,
+    /// The sorted accounts of the players, i.e. `1-S2-1-123,2-S2-1-456`.
+    pub toons: String,
+    /// The save date minus the length of the game, in seconds since the unix epoch. Each copy of
+    /// a game ends when its player leaves, so this is close but not equal across copies.
+    pub start_seconds: i64,
+}
+
+impl ReplayIdentity {
+    pub fn new(
+        data: &[u8],
+        details: &ReplayDetails,
+        date: &ReplayDate,
+        duration_seconds: u32,
+        random_seed: Option<u32>,
+    ) -> Self {
+        Self {
+            content_hash: Some(content_hash(data)),
+            random_seed,
+            toons: sorted_toons(details),
+            start_seconds: date.unix_seconds - i64::from(duration_seconds),
+        }
+    }
+
+    /// The same for every copy of a game, whoever saved it. Games without a random seed are
+    /// keyed on their start time, so their copies may get different keys.
+    pub fn game_key(&self) -> String {
+        match self.random_seed {
+            Some(random_seed) => format!("{:08x}-{}", random_seed, self.toons),
+            None => format!("{}-{}", self.start_seconds, self.toons),
+        }
+    }
+
+    /// Whether both replays are the same file or two copies of the same game.
+    pub fn is_same_game(&self, other: &ReplayIdentity) -> bool {
+        let same_file = self.content_hash.is_some() && self.content_hash == other.content_hash;
+        let same_game = self.toons == other.toons
+            && match (self.random_seed, other.random_seed) {
+                (Some(seed), Some(other_seed)) => seed == other_seed,
+                _ => {
+                    self.start_seconds.abs_diff(other.start_seconds)
+                        <= GAME_START_TOLERANCE_SECONDS as u64
+                }
+            };
+        same_file || same_game
+    }
+}
+
+/// The 64 bit FNV-1a hash of the file, stable across builds so it can be persisted.
+pub fn content_hash(data: &[u8]) -> String {
+    let hash = data.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The accounts of the players, sorted so that every copy of a game lists them the same way.
+pub fn sorted_toons(details: &ReplayDetails) -> String {
+    let mut toons: Vec<String> = details
+        .player_list
+        .iter()
+        .map(|player| player.toon.to_string())
+        .collect();
+    toons.sort();
+    toons.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameResult, PlayerColor, ReplayPlayer, Toon};
+
+    fn details(title: &str, toon_ids: &[u64]) -> ReplayDetails {
+        ReplayDetails {
+            title: title.to_string(),
+            map_file_name: String::new(),
+            description: String::new(),
+            is_blizzard_map: true,
+            player_list: toon_ids
+                .iter()
+                .map(|id| ReplayPlayer {
+                    name: format!("player{}", id),
+                    race: "Zerg".to_string(),
+                    result: GameResult::Win,
+                    team_id: 0,
+                    color: PlayerColor {
+                        r: 0,
+                        g: 0,
+                        b: 0,
+                        a: 255,
+                    },
+                    toon: Toon {
+                        region: 2,
+                        realm: 1,
+                        id: *id,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    fn date(unix_seconds: i64) -> ReplayDate {
+        ReplayDate {
+            unix_seconds,
+            local_offset_seconds: 0,
+        }
+    }
+
+    #[test]
+    fn copies_saved_by_teammates_are_the_same_game() {
+        let english = details("Altitude LE", &[1, 2, 3, 4]);
+        let french = details("Altitude EC", &[3, 4, 1, 2]);
+        let first = ReplayIdentity::new(b"first", &english, &date(1_672_531_200), 600, Some(42));
+        let second = ReplayIdentity::new(b"second", &french, &date(1_672_531_204), 600, Some(42));
+        assert!(first.is_same_game(&second));
+        assert_eq!(first.game_key(), second.game_key());
+    }
+
+    #[test]
+    fn copies_without_random_seed_use_the_start_time() {
+        let english = details("Altitude LE", &[1, 2]);
+        let french = details("Altitude EC", &[2, 1]);
+        let first = ReplayIdentity::new(b"first", &english, &date(1_672_531_200), 600, None);
+        let second = ReplayIdentity::new(b"second", &french, &date(1_672_531_207), 605, None);
+        assert!(first.is_same_game(&second));
+        let later = ReplayIdentity::new(b"later", &english, &date(1_672_534_800), 600, None);
+        assert!(!first.is_same_game(&later));
+    }
+
+    #[test]
+    fn different_seeds_are_different_games() {
+        let players = details("Altitude LE", &[1, 2]);
+        let first = ReplayIdentity::new(b"first", &players, &date(1_672_531_200), 600, Some(1));
+        let rematch = ReplayIdentity::new(b"second", &players, &date(1_672_531_210), 600, Some(2));
+        assert!(!first.is_same_game(&rematch));
+    }
+}
@@ -0,0 +1,235 @@
+//! Persistence of processed replays in the browser IndexedDB, so that they survive page reloads.
+//! The library keeps three object stores keyed by the same replay key:
+//! - `entries`: a small `LibraryEntry` to list the replays without reading their summaries.
+//! - `summaries`: the `ReplaySummary` JSON, enough to display the replay again.
+//! - `replays`: optionally, the SC2Replay file itself.
+
+use crate::{GameResult, ProcessedReplay, ReplayDate, ReplayError, ReplaySummary};
+use js_sys::{Array, Promise, Uint8Array};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Event, IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+const DB_NAME: &str = "cooper";
+const DB_VERSION: u32 = 1;
+const ENTRIES_STORE: &str = "entries";
+const SUMMARIES_STORE: &str = "summaries";
+const REPLAYS_STORE: &str = "replays";
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LibraryPlayer {
+    /// The name without the HTML escapes of the clan tag.
+    pub name: String,
+    pub race: String,
+    pub result: GameResult,
+    pub team_id: u8,
+}
+
+/// What the library panel lists about a stored replay.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    /// The key of the replay in the object stores.
+    pub key: String,
+    pub name: String,
+    pub date: ReplayDate,
+    pub map: String,
+    pub duration_seconds: u32,
+    pub players: Vec<LibraryPlayer>,
+    /// Whether the SC2Replay file is stored along with the summary.
+    pub has_replay_file: bool,
+}
+
+impl LibraryEntry {
+    pub fn new(replay: &ProcessedReplay, has_replay_file: bool) -> Self {
+        Self {
+            key: library_key(replay),
+            name: replay.name.clone(),
+            date: replay.date,
+            map: replay.details.map_name().to_string(),
+            duration_seconds: replay.duration_seconds(),
+            players: replay
+                .players()
+                .map(|(player_id, player)| LibraryPlayer {
+                    name: replay.roster.name_by_player_id(player_id),
+                    race: player.race.clone(),
+                    result: player.result,
+                    team_id: player.team_id,
+                })
+                .collect(),
+            has_replay_file,
+        }
+    }
+}
+
+/// The key a replay is stored under, copies of a game saved by different players share it.
+pub fn library_key(replay: &ProcessedReplay) -> String {
+    replay.identity.game_key()
+}
+
+/// Opens the library database, creating the object stores on first use.
+pub async fn open_library() -> Result<IdbDatabase, ReplayError> {
+    let factory = gloo::utils::window()
+        .indexed_db()
+        .map_err(library_error)?
+        .ok_or_else(|| ReplayError::Library("IndexedDB is not available".to_string()))?;
+    let open_request = factory
+        .open_with_u32(DB_NAME, DB_VERSION)
+        .map_err(library_error)?;
+    let on_upgrade_needed = wasm_bindgen::closure::Closure::once_into_js(move |event: Event| {
+        let request: IdbOpenDbRequest = match event.target() {
+            Some(target) => target.unchecked_into(),
+            None => return,
+        };
+        let db: IdbDatabase = match request.result() {
+            Ok(db) => db.unchecked_into(),
+            Err(_) => return,
+        };
+        for store in [ENTRIES_STORE, SUMMARIES_STORE, REPLAYS_STORE] {
+            if !db.object_store_names().contains(store) {
+                let _ = db.create_object_store(store);
+            }
+        }
+    });
+    open_request.set_onupgradeneeded(Some(on_upgrade_needed.unchecked_ref()));
+    let db = request_result(&open_request).await?;
+    Ok(db.unchecked_into())
+}
+
+/// Stores the summary of a replay, along with the SC2Replay file when `replay_file` is given.
+pub async fn store_replay(
+    db: &IdbDatabase,
+    entry: &LibraryEntry,
+    summary: &ReplaySummary,
+    replay_file: Option<&[u8]>,
+) -> Result<(), ReplayError> {
+    let key = JsValue::from_str(&entry.key);
+    let entry_json =
+        serde_json::to_string(entry).map_err(|err| ReplayError::Library(err.to_string()))?;
+    let summary_json = summary.to_json()?;
+    let stores = Array::of3(
+        &JsValue::from_str(ENTRIES_STORE),
+        &JsValue::from_str(SUMMARIES_STORE),
+        &JsValue::from_str(REPLAYS_STORE),
+    );
+    let transaction = db
+        .transaction_with_str_sequence_and_mode(&stores, IdbTransactionMode::Readwrite)
+        .map_err(library_error)?;
+    let requests = [
+        transaction
+            .object_store(ENTRIES_STORE)
+            .and_then(|store| store.put_with_key(&JsValue::from_str(&entry_json), &key)),
+        transaction
+            .object_store(SUMMARIES_STORE)
+            .and_then(|store| store.put_with_key(&JsValue::from_str(&summary_json), &key)),
+        transaction
+            .object_store(REPLAYS_STORE)
+            .and_then(|store| match replay_file {
+                Some(data) => store.put_with_key(&Uint8Array::from(data), &key),
+                // A previously stored file would not match the summary anymore.
+                None => store.delete(&key),
+            }),
+    ];
+    let mut pending = vec![];
+    for request in requests {
+        pending.push(request_future(&request.map_err(library_error)?));
+    }
+    for future in pending {
+        future.await.map_err(library_error)?;
+    }
+    Ok(())
+}
+
+/// The stored replays, most recent first.
+pub async fn list_entries(db: &IdbDatabase) -> Result<Vec<LibraryEntry>, ReplayError> {
+    let request = db
+        .transaction_with_str(ENTRIES_STORE)
+        .and_then(|transaction| transaction.object_store(ENTRIES_STORE))
+        .and_then(|store| store.get_all())
+        .map_err(library_error)?;
+    let values: Array = request_result(&request).await?.unchecked_into();
+    let mut entries = vec![];
+    for value in values.iter() {
+        let json = value.as_string().unwrap_or_default();
+        match serde_json::from_str::<LibraryEntry>(&json) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => gloo_console::log!("Skipping library entry", err.to_string()),
+        }
+    }
+    entries.sort_by(|a, b| b.date.cmp(&a.date));
+    Ok(entries)
+}
+
+pub async fn load_summary(db: &IdbDatabase, key: &str) -> Result<ReplaySummary, ReplayError> {
+    let value = get(db, SUMMARIES_STORE, key).await?;
+    let json = value
+        .as_string()
+        .ok_or_else(|| ReplayError::Library(format!("No summary stored for {}", key)))?;
+    ReplaySummary::from_json(json.as_bytes())
+}
+
+pub async fn load_replay_file(db: &IdbDatabase, key: &str) -> Result<Vec<u8>, ReplayError> {
+    let value = get(db, REPLAYS_STORE, key).await?;
+    match value.dyn_into::<Uint8Array>() {
+        Ok(data) => Ok(data.to_vec()),
+        Err(_) => Err(ReplayError::Library(format!(
+            "No SC2Replay file stored for {}",
+            key
+        ))),
+    }
+}
+
+/// Removes the replay from every object store.
+pub async fn delete_replay(db: &IdbDatabase, key: &str) -> Result<(), ReplayError> {
+    let key = JsValue::from_str(key);
+    for store in [ENTRIES_STORE, SUMMARIES_STORE, REPLAYS_STORE] {
+        let request = db
+            .transaction_with_str_and_mode(store, IdbTransactionMode::Readwrite)
+            .and_then(|transaction| transaction.object_store(store))
+            .and_then(|store| store.delete(&key))
+            .map_err(library_error)?;
+        request_result(&request).await?;
+    }
+    Ok(())
+}
+
+async fn get(db: &IdbDatabase, store: &str, key: &str) -> Result<JsValue, ReplayError> {
+    let request = db
+        .transaction_with_str(store)
+        .and_then(|transaction| transaction.object_store(store))
+        .and_then(|store| store.get(&JsValue::from_str(key)))
+        .map_err(library_error)?;
+    request_result(&request).await
+}
+
+async fn request_result(request: &IdbRequest) -> Result<JsValue, ReplayError> {
+    request_future(request).await.map_err(library_error)
+}
+
+/// Turns an IndexedDB request into a future, the requests report their outcome through the
+/// `success` and `error` events rather than promises. The handlers are attached right away, so
+/// several requests of a transaction can be issued before awaiting the first one.
+fn request_future(request: &IdbRequest) -> JsFuture {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = wasm_bindgen::closure::Closure::once_into_js(move || {
+            let result = success_request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::NULL, &result);
+        });
+        let error_request = request.clone();
+        let on_error = wasm_bindgen::closure::Closure::once_into_js(move || {
+            let error = match error_request.error() {
+                Ok(Some(error)) => JsValue::from(error.message()),
+                _ => JsValue::from_str("Unknown IndexedDB error"),
+            };
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise)
+}
+
+fn library_error(err: JsValue) -> ReplayError {
+    ReplayError::Library(err.as_string().unwrap_or_else(|| format!("{:?}", err)))
+}
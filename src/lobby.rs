@@ -0,0 +1,57 @@
+//! The parts of the s2protocol `InitData` cooper uses, the state of the lobby when the game
+//! started, copied into owned types like the `details` module does for the `Details`.
+
+use s2protocol::init_data::InitData;
+
+/// A lobby slot taken by a player, an AI or an observer.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LobbySlot {
+    /// The game and message events user id, AI players do not have one.
+    pub user_id: Option<u32>,
+    /// The account of the user, `<region>-S2-<realm>-<id>` like `Toon`, empty for AI players.
+    pub toon_handle: String,
+    pub working_set_slot_id: Option<u8>,
+    /// The name the user joined the lobby with, without the clan tag.
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ReplayLobby {
+    /// The seed of the game random number generator, the same in every copy of the replay.
+    pub random_seed: u32,
+    /// The size of the map, in cells.
+    pub map_size_x: u32,
+    pub map_size_y: u32,
+    pub slots: Vec<LobbySlot>,
+}
+
+impl From<&InitData> for ReplayLobby {
+    fn from(init_data: &InitData) -> Self {
+        let lobby_state = &init_data.sync_lobby_state.lobby_state;
+        let game_description = &init_data.sync_lobby_state.game_description;
+        let user_initial_data = &init_data.sync_lobby_state.user_initial_data;
+        let slots = lobby_state
+            .slots
+            .iter()
+            .map(|slot| {
+                let user_id = slot.user_id.map(|user_id| user_id as u32);
+                let name = user_id
+                    .and_then(|user_id| user_initial_data.get(user_id as usize))
+                    .map(|user| user.name.clone())
+                    .unwrap_or_default();
+                LobbySlot {
+                    user_id,
+                    toon_handle: slot.toon_handle.clone(),
+                    working_set_slot_id: slot.working_set_slot_id.map(|id| id as u8),
+                    name,
+                }
+            })
+            .collect();
+        Self {
+            random_seed: lobby_state.random_seed as u32,
+            map_size_x: game_description.map_size_x as u32,
+            map_size_y: game_description.map_size_y as u32,
+            slots,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Chat messages from the message events.
+
+use s2protocol::message_events::{GameEMessageRecipient, MessageEvent, ReplayMessageEvent};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum MessageRecipient {
+    All,
+    Allies,
+    Individual,
+    Battlenet,
+    Observers,
+}
+
+impl std::fmt::Display for MessageRecipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageRecipient::All => write!(f, "To All"),
+            MessageRecipient::Allies => write!(f, "To Allies"),
+            MessageRecipient::Individual => write!(f, "To Individual"),
+            MessageRecipient::Battlenet => write!(f, "To Battlenet"),
+            MessageRecipient::Observers => write!(f, "To Observers"),
+        }
+    }
+}
+
+impl From<&GameEMessageRecipient> for MessageRecipient {
+    fn from(recipient: &GameEMessageRecipient) -> Self {
+        match recipient {
+            GameEMessageRecipient::EAll => MessageRecipient::All,
+            GameEMessageRecipient::EAllies => MessageRecipient::Allies,
+            GameEMessageRecipient::EIndividual => MessageRecipient::Individual,
+            GameEMessageRecipient::EBattlenet => MessageRecipient::Battlenet,
+            GameEMessageRecipient::EObservers => MessageRecipient::Observers,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// The game loop the message was sent at.
+    pub frame: u32,
+    /// The game and message events user id of the sender.
+    pub user_id: u32,
+    pub recipient: MessageRecipient,
+    pub text: String,
+}
+
+/// Returns the chat messages, the events only contain the delta since the previous message so
+/// the frames are accumulated.
+pub fn extract_chat_messages(message_events: &[MessageEvent]) -> Vec<ChatMessage> {
+    let mut frame = 0u32;
+    let mut messages = vec![];
+    for event in message_events {
+        frame += event.delta as u32;
+        match &event.event {
+            ReplayMessageEvent::EChat(chat) => messages.push(ChatMessage {
+                frame,
+                user_id: event.user_id as u32,
+                recipient: MessageRecipient::from(&chat.m_recipient),
+                text: chat.m_string.clone(),
+            }),
+        }
+    }
+    messages
+}
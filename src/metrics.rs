@@ -0,0 +1,288 @@
+//! The metrics of the `GameSnapshot`s, to pick which ones are charted or exported without
+//! listing every field at each place.
+
+use crate::GameSnapshot;
+
+/// A value of the PlayerStats events, or one derived from them.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SnapshotMetric {
+    Minerals,
+    Vespene,
+    MineralIncome,
+    VespeneIncome,
+    SupplyUsed,
+    SupplyAvailable,
+    SupplyWorkers,
+    ActiveForceMinerals,
+    ActiveForceVespene,
+    MineralsUsedInProgressArmy,
+    MineralsUsedInProgressEconomy,
+    MineralsUsedInProgressTechnology,
+    VespeneUsedInProgressArmy,
+    VespeneUsedInProgressEconomy,
+    VespeneUsedInProgressTechnology,
+    MineralsUsedCurrentArmy,
+    MineralsUsedCurrentEconomy,
+    MineralsUsedCurrentTechnology,
+    VespeneUsedCurrentArmy,
+    VespeneUsedCurrentEconomy,
+    VespeneUsedCurrentTechnology,
+    MineralsFriendlyFireArmy,
+    MineralsFriendlyFireEconomy,
+    MineralsFriendlyFireTechnology,
+    VespeneFriendlyFireArmy,
+    VespeneFriendlyFireEconomy,
+    VespeneFriendlyFireTechnology,
+    MineralsLostArmy,
+    MineralsLostEconomy,
+    MineralsLostTechnology,
+    VespeneLostArmy,
+    VespeneLostEconomy,
+    VespeneLostTechnology,
+    MineralsKilledArmy,
+    MineralsKilledEconomy,
+    MineralsKilledTechnology,
+    VespeneKilledArmy,
+    VespeneKilledEconomy,
+    VespeneKilledTechnology,
+    // Derived from the fields above.
+    ArmyValue,
+    ResourcesLost,
+    ResourcesKilled,
+}
+
+impl SnapshotMetric {
+    pub const ALL: [SnapshotMetric; 42] = [
+        SnapshotMetric::Minerals,
+        SnapshotMetric::Vespene,
+        SnapshotMetric::MineralIncome,
+        SnapshotMetric::VespeneIncome,
+        SnapshotMetric::SupplyUsed,
+        SnapshotMetric::SupplyAvailable,
+        SnapshotMetric::SupplyWorkers,
+        SnapshotMetric::ActiveForceMinerals,
+        SnapshotMetric::ActiveForceVespene,
+        SnapshotMetric::MineralsUsedInProgressArmy,
+        SnapshotMetric::MineralsUsedInProgressEconomy,
+        SnapshotMetric::MineralsUsedInProgressTechnology,
+        SnapshotMetric::VespeneUsedInProgressArmy,
+        SnapshotMetric::VespeneUsedInProgressEconomy,
+        SnapshotMetric::VespeneUsedInProgressTechnology,
+        SnapshotMetric::MineralsUsedCurrentArmy,
+        SnapshotMetric::MineralsUsedCurrentEconomy,
+        SnapshotMetric::MineralsUsedCurrentTechnology,
+        SnapshotMetric::VespeneUsedCurrentArmy,
+        SnapshotMetric::VespeneUsedCurrentEconomy,
+        SnapshotMetric::VespeneUsedCurrentTechnology,
+        SnapshotMetric::MineralsFriendlyFireArmy,
+        SnapshotMetric::MineralsFriendlyFireEconomy,
+        SnapshotMetric::MineralsFriendlyFireTechnology,
+        SnapshotMetric::VespeneFriendlyFireArmy,
+        SnapshotMetric::VespeneFriendlyFireEconomy,
+        SnapshotMetric::VespeneFriendlyFireTechnology,
+        SnapshotMetric::MineralsLostArmy,
+        SnapshotMetric::MineralsLostEconomy,
+        SnapshotMetric::MineralsLostTechnology,
+        SnapshotMetric::VespeneLostArmy,
+        SnapshotMetric::VespeneLostEconomy,
+        SnapshotMetric::VespeneLostTechnology,
+        SnapshotMetric::MineralsKilledArmy,
+        SnapshotMetric::MineralsKilledEconomy,
+        SnapshotMetric::MineralsKilledTechnology,
+        SnapshotMetric::VespeneKilledArmy,
+        SnapshotMetric::VespeneKilledEconomy,
+        SnapshotMetric::VespeneKilledTechnology,
+        SnapshotMetric::ArmyValue,
+        SnapshotMetric::ResourcesLost,
+        SnapshotMetric::ResourcesKilled,
+    ];
+
+    /// The name of the `GameSnapshot` field, or of the method for derived metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SnapshotMetric::Minerals => "minerals",
+            SnapshotMetric::Vespene => "vespene",
+            SnapshotMetric::MineralIncome => "mineral_income",
+            SnapshotMetric::VespeneIncome => "vespene_income",
+            SnapshotMetric::SupplyUsed => "supply_used",
+            SnapshotMetric::SupplyAvailable => "supply_available",
+            SnapshotMetric::SupplyWorkers => "supply_workers",
+            SnapshotMetric::ActiveForceMinerals => "active_force_minerals",
+            SnapshotMetric::ActiveForceVespene => "active_force_vespene",
+            SnapshotMetric::MineralsUsedInProgressArmy => "minerals_used_in_progress_army",
+            SnapshotMetric::MineralsUsedInProgressEconomy => "minerals_used_in_progress_economy",
+            SnapshotMetric::MineralsUsedInProgressTechnology => {
+                "minerals_used_in_progress_technology"
+            }
+            SnapshotMetric::VespeneUsedInProgressArmy => "vespene_used_in_progress_army",
+            SnapshotMetric::VespeneUsedInProgressEconomy => "vespene_used_in_progress_economy",
+            SnapshotMetric::VespeneUsedInProgressTechnology => {
+                "vespene_used_in_progress_technology"
+            }
+            SnapshotMetric::MineralsUsedCurrentArmy => "minerals_used_current_army",
+            SnapshotMetric::MineralsUsedCurrentEconomy => "minerals_used_current_economy",
+            SnapshotMetric::MineralsUsedCurrentTechnology => "minerals_used_current_technology",
+            SnapshotMetric::VespeneUsedCurrentArmy => "vespene_used_current_army",
+            SnapshotMetric::VespeneUsedCurrentEconomy => "vespene_used_current_economy",
+            SnapshotMetric::VespeneUsedCurrentTechnology => "vespene_used_current_technology",
+            SnapshotMetric::MineralsFriendlyFireArmy => "minerals_friendly_fire_army",
+            SnapshotMetric::MineralsFriendlyFireEconomy => "minerals_friendly_fire_economy",
+            SnapshotMetric::MineralsFriendlyFireTechnology => "minerals_friendly_fire_technology",
+            SnapshotMetric::VespeneFriendlyFireArmy => "vespene_friendly_fire_army",
+            SnapshotMetric::VespeneFriendlyFireEconomy => "vespene_friendly_fire_economy",
+            SnapshotMetric::VespeneFriendlyFireTechnology => "vespene_friendly_fire_technology",
+            SnapshotMetric::MineralsLostArmy => "minerals_lost_army",
+            SnapshotMetric::MineralsLostEconomy => "minerals_lost_economy",
+            SnapshotMetric::MineralsLostTechnology => "minerals_lost_technology",
+            SnapshotMetric::VespeneLostArmy => "vespene_lost_army",
+            SnapshotMetric::VespeneLostEconomy => "vespene_lost_economy",
+            SnapshotMetric::VespeneLostTechnology => "vespene_lost_technology",
+            SnapshotMetric::MineralsKilledArmy => "minerals_killed_army",
+            SnapshotMetric::MineralsKilledEconomy => "minerals_killed_economy",
+            SnapshotMetric::MineralsKilledTechnology => "minerals_killed_technology",
+            SnapshotMetric::VespeneKilledArmy => "vespene_killed_army",
+            SnapshotMetric::VespeneKilledEconomy => "vespene_killed_economy",
+            SnapshotMetric::VespeneKilledTechnology => "vespene_killed_technology",
+            SnapshotMetric::ArmyValue => "army_value",
+            SnapshotMetric::ResourcesLost => "resources_lost",
+            SnapshotMetric::ResourcesKilled => "resources_killed",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnapshotMetric::Minerals => "Minerals",
+            SnapshotMetric::Vespene => "Vespene",
+            SnapshotMetric::MineralIncome => "Mineral income",
+            SnapshotMetric::VespeneIncome => "Vespene income",
+            SnapshotMetric::SupplyUsed => "Supply used",
+            SnapshotMetric::SupplyAvailable => "Supply available",
+            SnapshotMetric::SupplyWorkers => "Workers",
+            SnapshotMetric::ActiveForceMinerals => "Army minerals",
+            SnapshotMetric::ActiveForceVespene => "Army vespene",
+            SnapshotMetric::MineralsUsedInProgressArmy => "Minerals used in progress (army)",
+            SnapshotMetric::MineralsUsedInProgressEconomy => "Minerals used in progress (economy)",
+            SnapshotMetric::MineralsUsedInProgressTechnology => {
+                "Minerals used in progress (technology)"
+            }
+            SnapshotMetric::VespeneUsedInProgressArmy => "Vespene used in progress (army)",
+            SnapshotMetric::VespeneUsedInProgressEconomy => "Vespene used in progress (economy)",
+            SnapshotMetric::VespeneUsedInProgressTechnology => {
+                "Vespene used in progress (technology)"
+            }
+            SnapshotMetric::MineralsUsedCurrentArmy => "Minerals used (army)",
+            SnapshotMetric::MineralsUsedCurrentEconomy => "Minerals used (economy)",
+            SnapshotMetric::MineralsUsedCurrentTechnology => "Minerals used (technology)",
+            SnapshotMetric::VespeneUsedCurrentArmy => "Vespene used (army)",
+            SnapshotMetric::VespeneUsedCurrentEconomy => "Vespene used (economy)",
+            SnapshotMetric::VespeneUsedCurrentTechnology => "Vespene used (technology)",
+            SnapshotMetric::MineralsFriendlyFireArmy => "Minerals friendly fire (army)",
+            SnapshotMetric::MineralsFriendlyFireEconomy => "Minerals friendly fire (economy)",
+            SnapshotMetric::MineralsFriendlyFireTechnology => "Minerals friendly fire (technology)",
+            SnapshotMetric::VespeneFriendlyFireArmy => "Vespene friendly fire (army)",
+            SnapshotMetric::VespeneFriendlyFireEconomy => "Vespene friendly fire (economy)",
+            SnapshotMetric::VespeneFriendlyFireTechnology => "Vespene friendly fire (technology)",
+            SnapshotMetric::MineralsLostArmy => "Minerals lost (army)",
+            SnapshotMetric::MineralsLostEconomy => "Minerals lost (economy)",
+            SnapshotMetric::MineralsLostTechnology => "Minerals lost (technology)",
+            SnapshotMetric::VespeneLostArmy => "Vespene lost (army)",
+            SnapshotMetric::VespeneLostEconomy => "Vespene lost (economy)",
+            SnapshotMetric::VespeneLostTechnology => "Vespene lost (technology)",
+            SnapshotMetric::MineralsKilledArmy => "Minerals killed (army)",
+            SnapshotMetric::MineralsKilledEconomy => "Minerals killed (economy)",
+            SnapshotMetric::MineralsKilledTechnology => "Minerals killed (technology)",
+            SnapshotMetric::VespeneKilledArmy => "Vespene killed (army)",
+            SnapshotMetric::VespeneKilledEconomy => "Vespene killed (economy)",
+            SnapshotMetric::VespeneKilledTechnology => "Vespene killed (technology)",
+            SnapshotMetric::ArmyValue => "Army value",
+            SnapshotMetric::ResourcesLost => "Resources lost",
+            SnapshotMetric::ResourcesKilled => "Resources killed",
+        }
+    }
+
+    /// Whether the metric is computed from other fields rather than stored in the snapshot.
+    pub fn is_derived(&self) -> bool {
+        matches!(
+            self,
+            SnapshotMetric::ArmyValue
+                | SnapshotMetric::ResourcesLost
+                | SnapshotMetric::ResourcesKilled
+        )
+    }
+
+    pub fn value(&self, snapshot: &GameSnapshot) -> i32 {
+        match self {
+            SnapshotMetric::Minerals => snapshot.minerals,
+            SnapshotMetric::Vespene => snapshot.vespene,
+            SnapshotMetric::MineralIncome => snapshot.mineral_income,
+            SnapshotMetric::VespeneIncome => snapshot.vespene_income,
+            SnapshotMetric::SupplyUsed => snapshot.supply_used,
+            SnapshotMetric::SupplyAvailable => snapshot.supply_available,
+            SnapshotMetric::SupplyWorkers => snapshot.supply_workers,
+            SnapshotMetric::ActiveForceMinerals => snapshot.active_force_minerals,
+            SnapshotMetric::ActiveForceVespene => snapshot.active_force_vespene,
+            SnapshotMetric::MineralsUsedInProgressArmy => snapshot.minerals_used_in_progress_army,
+            SnapshotMetric::MineralsUsedInProgressEconomy => {
+                snapshot.minerals_used_in_progress_economy
+            }
+            SnapshotMetric::MineralsUsedInProgressTechnology => {
+                snapshot.minerals_used_in_progress_technology
+            }
+            SnapshotMetric::VespeneUsedInProgressArmy => snapshot.vespene_used_in_progress_army,
+            SnapshotMetric::VespeneUsedInProgressEconomy => {
+                snapshot.vespene_used_in_progress_economy
+            }
+            SnapshotMetric::VespeneUsedInProgressTechnology => {
+                snapshot.vespene_used_in_progress_technology
+            }
+            SnapshotMetric::MineralsUsedCurrentArmy => snapshot.minerals_used_current_army,
+            SnapshotMetric::MineralsUsedCurrentEconomy => snapshot.minerals_used_current_economy,
+            SnapshotMetric::MineralsUsedCurrentTechnology => {
+                snapshot.minerals_used_current_technology
+            }
+            SnapshotMetric::VespeneUsedCurrentArmy => snapshot.vespene_used_current_army,
+            SnapshotMetric::VespeneUsedCurrentEconomy => snapshot.vespene_used_current_economy,
+            SnapshotMetric::VespeneUsedCurrentTechnology => {
+                snapshot.vespene_used_current_technology
+            }
+            SnapshotMetric::MineralsFriendlyFireArmy => snapshot.minerals_friendly_fire_army,
+            SnapshotMetric::MineralsFriendlyFireEconomy => snapshot.minerals_friendly_fire_economy,
+            SnapshotMetric::MineralsFriendlyFireTechnology => {
+                snapshot.minerals_friendly_fire_technology
+            }
+            SnapshotMetric::VespeneFriendlyFireArmy => snapshot.vespene_friendly_fire_army,
+            SnapshotMetric::VespeneFriendlyFireEconomy => snapshot.vespene_friendly_fire_economy,
+            SnapshotMetric::VespeneFriendlyFireTechnology => {
+                snapshot.vespene_friendly_fire_technology
+            }
+            SnapshotMetric::MineralsLostArmy => snapshot.minerals_lost_army,
+            SnapshotMetric::MineralsLostEconomy => snapshot.minerals_lost_economy,
+            SnapshotMetric::MineralsLostTechnology => snapshot.minerals_lost_technology,
+            SnapshotMetric::VespeneLostArmy => snapshot.vespene_lost_army,
+            SnapshotMetric::VespeneLostEconomy => snapshot.vespene_lost_economy,
+            SnapshotMetric::VespeneLostTechnology => snapshot.vespene_lost_technology,
+            SnapshotMetric::MineralsKilledArmy => snapshot.minerals_killed_army,
+            SnapshotMetric::MineralsKilledEconomy => snapshot.minerals_killed_economy,
+            SnapshotMetric::MineralsKilledTechnology => snapshot.minerals_killed_technology,
+            SnapshotMetric::VespeneKilledArmy => snapshot.vespene_killed_army,
+            SnapshotMetric::VespeneKilledEconomy => snapshot.vespene_killed_economy,
+            SnapshotMetric::VespeneKilledTechnology => snapshot.vespene_killed_technology,
+            SnapshotMetric::ArmyValue => {
+                snapshot.active_force_minerals + snapshot.active_force_vespene
+            }
+            SnapshotMetric::ResourcesLost => snapshot.resources_lost(),
+            SnapshotMetric::ResourcesKilled => snapshot.resources_killed(),
+        }
+    }
+
+    /// Looks a metric up by its `name`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.name() == name)
+    }
+}
+
+impl std::fmt::Display for SnapshotMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
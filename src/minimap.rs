@@ -0,0 +1,165 @@
+use crate::canvas::{container_width, resize_canvas, PlotLayout, ResizeWatcher};
+use gloo_console::log;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters_canvas::CanvasBackend;
+use web_sys::HtmlCanvasElement;
+use yew::prelude::*;
+
+pub enum MinimapMsg {
+    Redraw,
+}
+
+/// A unit or structure drawn on the minimap, in map cells.
+#[derive(PartialEq)]
+pub struct MinimapPoint {
+    pub x: u32,
+    pub y: u32,
+    pub color: RGBColor,
+    pub is_structure: bool,
+}
+
+/// A square of the heatmap drawn over the minimap, more opaque the higher the `intensity`.
+#[derive(PartialEq)]
+pub struct MinimapHeatCell {
+    pub x: u32,
+    pub y: u32,
+    /// The side of the square, in map cells.
+    pub size: u32,
+    /// Between 0 and 1.
+    pub intensity: f64,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MinimapProperties {
+    pub points: Vec<MinimapPoint>,
+    #[prop_or_default]
+    pub heatmap: Vec<MinimapHeatCell>,
+    #[prop_or(RGBColor(255, 0, 0))]
+    pub heatmap_color: RGBColor,
+    /// The size of the map in cells, the minimap keeps its aspect ratio.
+    pub map_width: u32,
+    pub map_height: u32,
+    #[prop_or_default]
+    pub layout: PlotLayout,
+}
+
+/// Draws the positions of the units at a point of the game, the map y axis points up like the
+/// plots y axis. The heatmap, if any, is drawn over the units.
+pub struct Minimap {
+    canvas: NodeRef,
+    container: NodeRef,
+    resize_watcher: ResizeWatcher,
+}
+
+impl Component for Minimap {
+    type Message = MinimapMsg;
+    type Properties = MinimapProperties;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(MinimapMsg::Redraw);
+        Minimap {
+            canvas: NodeRef::default(),
+            container: NodeRef::default(),
+            resize_watcher: ResizeWatcher::new(ctx.link().callback(|_| MinimapMsg::Redraw)),
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props() != old_props {
+            ctx.link().send_message(MinimapMsg::Redraw);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            MinimapMsg::Redraw => {
+                let element: HtmlCanvasElement = match self.canvas.cast() {
+                    Some(element) => element,
+                    None => return false,
+                };
+
+                let map_width = ctx.props().map_width.max(1);
+                let map_height = ctx.props().map_height.max(1);
+                let (max_width, max_height) = ctx
+                    .props()
+                    .layout
+                    .plot_size(container_width(self.container.cast()));
+                let cell_size = (max_width as f64 / map_width as f64)
+                    .min(max_height as f64 / map_height as f64);
+                let css_width = (map_width as f64 * cell_size).round() as u32;
+                let css_height = (map_height as f64 * cell_size).round() as u32;
+                let scale = resize_canvas(&element, css_width, css_height);
+                self.resize_watcher.watch_device_pixel_ratio();
+
+                let backend = match CanvasBackend::with_canvas_object(element) {
+                    Some(backend) => backend,
+                    None => return false,
+                };
+                let drawing_area = backend.into_drawing_area();
+                if let Err(err) = draw_minimap(&drawing_area, ctx.props(), cell_size * scale) {
+                    log!("Unable to draw the minimap", err.to_string());
+                }
+                false
+            }
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        html!(
+            <div ref={self.container.clone()}>
+                <canvas ref = {self.canvas.clone()}/>
+            </div>
+        )
+    }
+}
+
+/// Draws the units and the heatmap, `cell_size` is the size of a map cell in backend pixels.
+fn draw_minimap<DB: DrawingBackend>(
+    drawing_area: &DrawingArea<DB, Shift>,
+    props: &MinimapProperties,
+    cell_size: f64,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let px = |size: f64| size.round().max(1.) as i32;
+    drawing_area.fill(&RGBColor(30, 30, 30))?;
+
+    let mut chart = ChartBuilder::on(drawing_area)
+        .build_cartesian_2d(0..props.map_width.max(1), 0..props.map_height.max(1))?;
+
+    // Structures first so that the units stay visible on top of them.
+    chart.draw_series(
+        props
+            .points
+            .iter()
+            .filter(|point| point.is_structure)
+            .map(|point| {
+                let half = px(cell_size * 1.5);
+                EmptyElement::at((point.x, point.y))
+                    + Rectangle::new([(-half, -half), (half, half)], point.color.filled())
+            }),
+    )?;
+    chart.draw_series(
+        props
+            .points
+            .iter()
+            .filter(|point| !point.is_structure)
+            .map(|point| {
+                Circle::new(
+                    (point.x, point.y),
+                    px(cell_size * 0.75),
+                    point.color.filled(),
+                )
+            }),
+    )?;
+    let heatmap_color = props.heatmap_color;
+    chart.draw_series(props.heatmap.iter().map(|cell| {
+        Rectangle::new(
+            [(cell.x, cell.y), (cell.x + cell.size, cell.y + cell.size)],
+            heatmap_color.mix(0.2 + 0.6 * cell.intensity).filled(),
+        )
+    }))?;
+    Ok(())
+}
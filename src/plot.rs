@@ -0,0 +1,415 @@
+use crate::canvas::{
+    container_width, download_data_url, download_text, resize_canvas, PlotLayout, ResizeWatcher,
+};
+use crate::GameSpeed;
+use gloo_console::log;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters_canvas::CanvasBackend;
+use web_sys::HtmlCanvasElement;
+use yew::prelude::*;
+
+pub enum PlotMsg {
+    Redraw,
+    /// The mouse moved over the canvas, contains the offset in pixels.
+    MouseMove(i32, i32),
+    /// A drag to zoom started.
+    MouseDown(i32, i32),
+    /// A drag to zoom finished.
+    MouseUp(i32, i32),
+    MouseLeave,
+    /// Resets the zoom.
+    DoubleClick,
+    /// Downloads the canvas as a PNG image.
+    DownloadPng,
+    /// Draws the series again with the SVG backend and downloads it.
+    DownloadSvg,
+    Nothing,
+}
+
+#[derive(PartialEq)]
+pub struct PlotData {
+    pub x: u32,
+    pub y: i32,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum LineStyle {
+    Solid,
+    Dashed,
+}
+
+#[derive(PartialEq)]
+pub struct PlotSeries {
+    pub series: Vec<PlotData>,
+    pub color: RGBColor,
+    /// Shown in the legend and the tooltip, i.e. the player name and the metric.
+    pub label: String,
+    pub style: LineStyle,
+}
+
+impl PlotSeries {
+    /// The value of the series at frame `x`, that is the last data point at or before it.
+    pub fn value_at(&self, x: u32) -> Option<i32> {
+        self.series
+            .iter()
+            .take_while(|plot_data| plot_data.x <= x)
+            .last()
+            .map(|plot_data| plot_data.y)
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PlotProperties {
+    pub series: Vec<PlotSeries>,
+    /// Used to label the x axis, which contains game loops, with the in-game time.
+    #[prop_or_default]
+    pub game_speed: GameSpeed,
+    /// The title of the y axis, with its unit.
+    #[prop_or_default]
+    pub y_desc: String,
+    /// The hovered frame, shared by the plots of a replay so that the crosshair is synchronised.
+    #[prop_or_default]
+    pub cursor: Option<u32>,
+    #[prop_or_default]
+    pub on_cursor: Callback<Option<u32>>,
+    /// The zoomed in range of frames, the whole game when None.
+    #[prop_or_default]
+    pub x_range: Option<(u32, u32)>,
+    #[prop_or_default]
+    pub on_zoom: Callback<Option<(u32, u32)>>,
+    #[prop_or_default]
+    pub layout: PlotLayout,
+    /// The file name, without extension, of the exported images.
+    #[prop_or_default]
+    pub export_name: String,
+}
+
+/// Converts a pixel of the canvas to the coordinates of the chart.
+type CoordTrans = Box<dyn Fn((i32, i32)) -> Option<(u32, i32)>>;
+
+pub struct Plot {
+    canvas: NodeRef,
+    container: NodeRef,
+    coord_trans: Option<CoordTrans>,
+    /// The frame where a drag to zoom started.
+    drag_start: Option<u32>,
+    /// The device pixel ratio the canvas was last drawn with.
+    scale: f64,
+    resize_watcher: ResizeWatcher,
+}
+
+impl Plot {
+    fn export_name(&self, ctx: &Context<Self>) -> String {
+        if ctx.props().export_name.is_empty() {
+            "plot".to_string()
+        } else {
+            ctx.props().export_name.clone()
+        }
+    }
+
+    /// Converts the offset of a mouse event, in CSS pixels, to a frame.
+    fn frame_at(&self, x: i32, y: i32) -> Option<u32> {
+        let x = (x as f64 * self.scale).round() as i32;
+        let y = (y as f64 * self.scale).round() as i32;
+        self.coord_trans
+            .as_ref()
+            .and_then(|coord_trans| coord_trans((x, y)))
+            .map(|(frame, _)| frame)
+    }
+}
+
+impl Component for Plot {
+    type Message = PlotMsg;
+    type Properties = PlotProperties;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(PlotMsg::Redraw);
+        Plot {
+            canvas: NodeRef::default(),
+            container: NodeRef::default(),
+            coord_trans: None,
+            drag_start: None,
+            scale: 1.,
+            resize_watcher: ResizeWatcher::new(ctx.link().callback(|_| PlotMsg::Redraw)),
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        // The callbacks are recreated on every render of the parent, only redraw when the
+        // drawn data changes.
+        if props.series != old_props.series
+            || props.game_speed != old_props.game_speed
+            || props.y_desc != old_props.y_desc
+            || props.cursor != old_props.cursor
+            || props.x_range != old_props.x_range
+            || props.layout != old_props.layout
+        {
+            ctx.link().send_message(PlotMsg::Redraw);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            PlotMsg::Redraw => {
+                let element: HtmlCanvasElement = match self.canvas.cast() {
+                    Some(element) => element,
+                    None => return false,
+                };
+
+                let (css_width, css_height) = ctx
+                    .props()
+                    .layout
+                    .plot_size(container_width(self.container.cast()));
+                let scale = resize_canvas(&element, css_width, css_height);
+                self.scale = scale;
+                self.resize_watcher.watch_device_pixel_ratio();
+
+                let backend = match CanvasBackend::with_canvas_object(element) {
+                    Some(backend) => backend,
+                    None => return false,
+                };
+                let drawing_area = backend.into_drawing_area();
+                match draw_plot(&drawing_area, ctx.props(), scale, self.drag_start) {
+                    Ok(chart) => self.coord_trans = Some(Box::new(chart.into_coord_trans())),
+                    Err(err) => {
+                        log!("Unable to draw the plot", err.to_string());
+                        self.coord_trans = None;
+                    }
+                }
+                false
+            }
+            PlotMsg::MouseMove(x, y) => {
+                let frame = self.frame_at(x, y);
+                if frame.is_some() {
+                    ctx.props().on_cursor.emit(frame);
+                }
+                false
+            }
+            PlotMsg::MouseDown(x, y) => {
+                self.drag_start = self.frame_at(x, y);
+                false
+            }
+            PlotMsg::MouseUp(x, y) => {
+                if let (Some(drag_start), Some(frame)) =
+                    (self.drag_start.take(), self.frame_at(x, y))
+                {
+                    if drag_start != frame {
+                        ctx.props()
+                            .on_zoom
+                            .emit(Some((drag_start.min(frame), drag_start.max(frame))));
+                    }
+                }
+                ctx.link().send_message(PlotMsg::Redraw);
+                false
+            }
+            PlotMsg::MouseLeave => {
+                self.drag_start = None;
+                ctx.props().on_cursor.emit(None);
+                false
+            }
+            PlotMsg::DoubleClick => {
+                ctx.props().on_zoom.emit(None);
+                false
+            }
+            PlotMsg::DownloadPng => {
+                let data_url = self
+                    .canvas
+                    .cast::<HtmlCanvasElement>()
+                    .and_then(|element| element.to_data_url().ok());
+                if let Some(data_url) = data_url {
+                    download_data_url(&format!("{}.png", self.export_name(ctx)), &data_url);
+                }
+                false
+            }
+            PlotMsg::DownloadSvg => {
+                let (css_width, css_height) = ctx
+                    .props()
+                    .layout
+                    .plot_size(container_width(self.container.cast()));
+                let mut svg = String::new();
+                {
+                    let backend = SVGBackend::with_string(&mut svg, (css_width, css_height));
+                    let drawing_area = backend.into_drawing_area();
+                    let drawn = draw_plot(&drawing_area, ctx.props(), 1., None)
+                        .and_then(|_| drawing_area.present());
+                    if let Err(err) = drawn {
+                        log!("Unable to draw the SVG plot", err.to_string());
+                        return false;
+                    }
+                }
+                download_text(
+                    &format!("{}.svg", self.export_name(ctx)),
+                    "image/svg+xml",
+                    &svg,
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let tooltip = match ctx.props().cursor {
+            Some(cursor) => html! {
+                <div class="small text-start" title={ format!("frame: {}", cursor) }>
+                  { ctx.props().game_speed.format_loops(cursor) }
+                  { for ctx.props().series.iter().filter_map(|plot| plot.value_at(cursor).map(|value| html! {
+                      <span class="ms-2" style={ format!("color:rgb({},{},{})", plot.color.0, plot.color.1, plot.color.2) }>
+                        { format!("\u{25A0} {}: {}", plot.label, value) }
+                      </span>
+                  })) }
+                </div>
+            },
+            None => html! { <div class="small">{ "\u{00A0}" }</div> },
+        };
+        html!(
+            <div ref={self.container.clone()}>
+                <div class="text-end">
+                  <button type="button" class="btn btn-outline-secondary btn-sm me-1" onclick={link.callback(|_| PlotMsg::DownloadPng)}>{ "Download PNG" }</button>
+                  <button type="button" class="btn btn-outline-secondary btn-sm" onclick={link.callback(|_| PlotMsg::DownloadSvg)}>{ "Download SVG" }</button>
+                </div>
+                { tooltip }
+                <canvas ref = {self.canvas.clone()}
+                    onmousemove={link.callback(|event: MouseEvent| PlotMsg::MouseMove(event.offset_x(), event.offset_y()))}
+                    onmousedown={link.callback(|event: MouseEvent| PlotMsg::MouseDown(event.offset_x(), event.offset_y()))}
+                    onmouseup={link.callback(|event: MouseEvent| PlotMsg::MouseUp(event.offset_x(), event.offset_y()))}
+                    onmouseleave={link.callback(|_: MouseEvent| PlotMsg::MouseLeave)}
+                    ondblclick={link.callback(|_: MouseEvent| PlotMsg::DoubleClick)}
+                />
+            </div>
+        )
+    }
+}
+
+type PlotChart<'a, DB> = ChartContext<'a, DB, Cartesian2d<RangedCoordu32, RangedCoordi32>>;
+
+/// Draws the series on any plotters backend, the canvas for display and SVG for exports.
+/// `scale` converts CSS pixels to backend pixels, `drag_start` draws the zoom selection.
+/// Fails when the backend does, i.e. on a canvas too small for the chart.
+fn draw_plot<'a, DB: DrawingBackend>(
+    drawing_area: &'a DrawingArea<DB, Shift>,
+    props: &PlotProperties,
+    scale: f64,
+    drag_start: Option<u32>,
+) -> Result<PlotChart<'a, DB>, DrawingAreaErrorKind<DB::ErrorType>> {
+    // Sizes in pixels are given in CSS pixels and scaled to the backend pixels.
+    let px = |size: f64| (size * scale).round() as u32;
+
+    drawing_area.fill(&RGBColor(200, 200, 200))?;
+
+    let (min_x, max_x) = match props.x_range {
+        Some(x_range) => x_range,
+        None => (
+            0,
+            props
+                .series
+                .iter()
+                .map(|s| s.series.iter().map(|plot_data| plot_data.x).max())
+                .max()
+                .flatten()
+                .unwrap_or_default(),
+        ),
+    };
+    let max_x = max_x.max(min_x + 1);
+    let is_visible = |plot_data: &&PlotData| plot_data.x >= min_x && plot_data.x <= max_x;
+    let max_y = props
+        .series
+        .iter()
+        .map(|s| {
+            s.series
+                .iter()
+                .filter(is_visible)
+                .map(|plot_data| plot_data.y)
+                .max()
+        })
+        .max()
+        .flatten()
+        .unwrap_or_default()
+        .max(1);
+
+    let mut chart = ChartBuilder::on(drawing_area)
+        .margin(px(5.))
+        .x_label_area_size(px(40.))
+        .y_label_area_size(px(60.))
+        .build_cartesian_2d(min_x..max_x, 0..max_y)?;
+
+    let game_speed = props.game_speed;
+    chart
+        .configure_mesh()
+        .x_label_formatter(&|x| game_speed.format_loops(*x))
+        .x_desc("Game time (mm:ss)")
+        .y_desc(props.y_desc.as_str())
+        .label_style(("sans-serif", 12. * scale))
+        .axis_desc_style(("sans-serif", 15. * scale))
+        .draw()?;
+
+    let dash_size = px(6.) as i32;
+    let dash_spacing = px(4.) as i32;
+    let legend_width = px(20.) as i32;
+    let legend_stroke = px(2.);
+    for plot in props.series.iter() {
+        let points = plot
+            .series
+            .iter()
+            .filter(is_visible)
+            .map(|plot_data| (plot_data.x, plot_data.y));
+        let style = plot.color.mix(0.5).stroke_width(px(2.));
+        let annotation = match plot.style {
+            LineStyle::Solid => chart.draw_series(LineSeries::new(points, style)),
+            LineStyle::Dashed => chart.draw_series(DashedLineSeries::new(
+                points,
+                dash_size,
+                dash_spacing,
+                style,
+            )),
+        };
+        // The legend sample is two segments, joined for solid lines and dashed like the series
+        // for dashed lines.
+        let (segment, spacing) = match plot.style {
+            LineStyle::Solid => (legend_width / 2, 0),
+            LineStyle::Dashed => (dash_size, dash_spacing),
+        };
+        let legend_style = plot.color.stroke_width(legend_stroke);
+        annotation?.label(plot.label.clone()).legend(move |(x, y)| {
+            EmptyElement::at((x, y))
+                + PathElement::new(vec![(0, 0), (segment, 0)], legend_style)
+                + PathElement::new(
+                    vec![(segment + spacing, 0), (2 * segment + spacing, 0)],
+                    legend_style,
+                )
+        });
+    }
+
+    if !props.series.is_empty() {
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .label_font(("sans-serif", 12. * scale))
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+
+    if let Some(cursor) = props.cursor {
+        if let Some(drag_start) = drag_start {
+            chart.draw_series(std::iter::once(Rectangle::new(
+                [(drag_start.min(cursor), 0), (drag_start.max(cursor), max_y)],
+                BLACK.mix(0.1).filled(),
+            )))?;
+        }
+        if cursor >= min_x && cursor <= max_x {
+            chart.draw_series(std::iter::once(PathElement::new(
+                vec![(cursor, 0), (cursor, max_y)],
+                BLACK.mix(0.5),
+            )))?;
+        }
+    }
+
+    Ok(chart)
+}
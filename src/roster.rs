@@ -0,0 +1,292 @@
+//! Mapping between the different ids a player has across the replay files.
+//! - The details player list has the slot id (`working_set_slot_id`), the team id and the
+//!   account (toon) of the players.
+//! - The init data lobby slots have the `user_id` and the account of every user, observers
+//!   included.
+//! - The tracker events identify players by `player_id`, starting at 1.
+//! - The game and message events identify users by `user_id`, observers included.
+//!
+//! The details players are matched to their lobby slot by account, or by slot id for AI players.
+//! The tracker PlayerSetup events give the `player_id`, older replays that lack them fall back
+//! to the player list order. The lobby users that are not players are the observers.
+
+use crate::lobby::{LobbySlot, ReplayLobby};
+use crate::{minor_player_clan_unescape, ReplayPlayer};
+use s2protocol::details::Details;
+use s2protocol::tracker_events::{ReplayTrackerEvent, TrackerEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The details player control value for computer players.
+const CONTROL_COMPUTER: u8 = 3;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RosterEntry {
+    /// The tracker events player id.
+    pub player_id: u8,
+    /// The game and message events user id, AI players do not have one.
+    pub user_id: Option<u32>,
+    /// The lobby slot id, the `working_set_slot_id` of the details.
+    pub slot_id: Option<u8>,
+    pub team_id: u8,
+    /// The name without the HTML escapes of the clan tag.
+    pub name: String,
+    pub is_ai: bool,
+    /// The index of the player in `Details::player_list`.
+    pub details_idx: usize,
+}
+
+/// A user that watched the game without playing.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RosterObserver {
+    /// The game and message events user id.
+    pub user_id: u32,
+    /// The name without the HTML escapes of the clan tag.
+    pub name: String,
+}
+
+/// The fields of a details player the roster is resolved from.
+#[derive(Debug, PartialEq, Clone)]
+struct RosterPlayer {
+    name: String,
+    toon_handle: String,
+    team_id: u8,
+    slot_id: Option<u8>,
+    is_ai: bool,
+}
+
+/// The ids of a tracker PlayerSetup event.
+#[derive(Debug, PartialEq, Clone)]
+struct PlayerSetupIds {
+    player_id: u8,
+    user_id: Option<u32>,
+    slot_id: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PlayerRoster {
+    /// Ordered by `details_idx`.
+    entries: Vec<RosterEntry>,
+    observers: Vec<RosterObserver>,
+}
+
+impl PlayerRoster {
+    pub fn new(details: &Details, lobby: &ReplayLobby, tracker_events: &[TrackerEvent]) -> Self {
+        let players: Vec<RosterPlayer> = details
+            .player_list
+            .iter()
+            .map(|player| RosterPlayer {
+                name: player.name.clone(),
+                toon_handle: ReplayPlayer::from(player).toon.to_string(),
+                team_id: player.team_id,
+                slot_id: player.working_set_slot_id,
+                is_ai: player.control == CONTROL_COMPUTER,
+            })
+            .collect();
+        let setups: Vec<PlayerSetupIds> = tracker_events
+            .iter()
+            .filter_map(|event| match &event.event {
+                ReplayTrackerEvent::PlayerSetup(setup) => Some(PlayerSetupIds {
+                    player_id: setup.player_id,
+                    user_id: setup.user_id,
+                    slot_id: setup.slot_id,
+                }),
+                _ => None,
+            })
+            .collect();
+        Self::resolve(&players, &lobby.slots, &setups)
+    }
+
+    fn resolve(players: &[RosterPlayer], slots: &[LobbySlot], setups: &[PlayerSetupIds]) -> Self {
+        let mut entries = vec![];
+        let mut player_slots = vec![];
+        for (details_idx, player) in players.iter().enumerate() {
+            let slot = slots
+                .iter()
+                .position(|slot| !player.is_ai && slot.toon_handle == player.toon_handle)
+                .or_else(|| {
+                    slots.iter().position(|slot| {
+                        player.slot_id.is_some() && slot.working_set_slot_id == player.slot_id
+                    })
+                });
+            let slot_user_id = slot.and_then(|slot| slots[slot].user_id);
+            player_slots.extend(slot);
+            let setup = setups
+                .iter()
+                .find(|setup| {
+                    player.slot_id.is_some() && setup.slot_id == player.slot_id.map(u32::from)
+                })
+                .or_else(|| {
+                    setups
+                        .iter()
+                        .find(|setup| slot_user_id.is_some() && setup.user_id == slot_user_id)
+                });
+            let user_id = if player.is_ai {
+                None
+            } else {
+                setup.and_then(|setup| setup.user_id).or(slot_user_id)
+            };
+            entries.push(RosterEntry {
+                player_id: setup.map_or(details_idx as u8 + 1, |setup| setup.player_id),
+                user_id,
+                slot_id: player.slot_id,
+                team_id: player.team_id,
+                name: minor_player_clan_unescape(&player.name),
+                is_ai: player.is_ai,
+                details_idx,
+            });
+        }
+        let observers = slots
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !player_slots.contains(idx))
+            .filter_map(|(_, slot)| {
+                Some(RosterObserver {
+                    user_id: slot.user_id?,
+                    name: minor_player_clan_unescape(&slot.name),
+                })
+            })
+            .collect();
+        Self { entries, observers }
+    }
+
+    /// Rebuilds a roster from previously resolved entries, i.e. from a `ReplaySummary`.
+    pub fn from_entries(mut entries: Vec<RosterEntry>, observers: Vec<RosterObserver>) -> Self {
+        entries.sort_by_key(|entry| entry.details_idx);
+        Self { entries, observers }
+    }
+
+    /// The players in the order of the details player list.
+    pub fn iter(&self) -> impl Iterator<Item = &RosterEntry> {
+        self.entries.iter()
+    }
+
+    pub fn by_player_id(&self, player_id: u8) -> Option<&RosterEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.player_id == player_id)
+    }
+
+    pub fn by_user_id(&self, user_id: u32) -> Option<&RosterEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.user_id == Some(user_id))
+    }
+
+    /// Resolves the name of a tracker events player id.
+    pub fn name_by_player_id(&self, player_id: u8) -> String {
+        match self.by_player_id(player_id) {
+            Some(entry) => entry.name.clone(),
+            None => format!("Player {}", player_id),
+        }
+    }
+
+    pub fn observers(&self) -> impl Iterator<Item = &RosterObserver> {
+        self.observers.iter()
+    }
+
+    /// Resolves the name of a game or message events user id, players or observers.
+    pub fn name_by_user_id(&self, user_id: u32) -> String {
+        if let Some(entry) = self.by_user_id(user_id) {
+            return entry.name.clone();
+        }
+        match self
+            .observers
+            .iter()
+            .find(|observer| observer.user_id == user_id)
+        {
+            Some(observer) => observer.name.clone(),
+            None => format!("Observer {}", user_id),
+        }
+    }
+
+    /// The players grouped by their team id.
+    pub fn teams(&self) -> BTreeMap<u8, Vec<&RosterEntry>> {
+        let mut teams: BTreeMap<u8, Vec<&RosterEntry>> = BTreeMap::new();
+        for entry in self.iter() {
+            teams.entry(entry.team_id).or_default().push(entry);
+        }
+        teams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str, toon_handle: &str, team_id: u8, slot_id: u8) -> RosterPlayer {
+        RosterPlayer {
+            name: name.to_string(),
+            toon_handle: toon_handle.to_string(),
+            team_id,
+            slot_id: Some(slot_id),
+            is_ai: false,
+        }
+    }
+
+    fn slot(user_id: u32, name: &str, toon_handle: &str, slot_id: u8) -> LobbySlot {
+        LobbySlot {
+            user_id: Some(user_id),
+            toon_handle: toon_handle.to_string(),
+            working_set_slot_id: Some(slot_id),
+            name: name.to_string(),
+        }
+    }
+
+    /// Two players and an observer, who joined the lobby between them.
+    fn lobby() -> (Vec<RosterPlayer>, Vec<LobbySlot>) {
+        let players = vec![
+            player("Alice", "2-S2-1-1", 0, 0),
+            player("Bob", "2-S2-1-2", 1, 2),
+        ];
+        let slots = vec![
+            slot(0, "Alice", "2-S2-1-1", 0),
+            slot(1, "Caster", "2-S2-1-3", 1),
+            slot(2, "Bob", "2-S2-1-2", 2),
+        ];
+        (players, slots)
+    }
+
+    #[test]
+    fn observers_are_named() {
+        let (players, slots) = lobby();
+        let setups = vec![
+            PlayerSetupIds {
+                player_id: 1,
+                user_id: Some(0),
+                slot_id: Some(0),
+            },
+            PlayerSetupIds {
+                player_id: 2,
+                user_id: Some(2),
+                slot_id: Some(2),
+            },
+        ];
+        let roster = PlayerRoster::resolve(&players, &slots, &setups);
+        assert_eq!(roster.name_by_user_id(0), "Alice");
+        assert_eq!(roster.name_by_user_id(1), "Caster");
+        assert_eq!(roster.name_by_user_id(2), "Bob");
+        assert_eq!(
+            roster.observers().collect::<Vec<_>>(),
+            vec![&RosterObserver {
+                user_id: 1,
+                name: "Caster".to_string(),
+            }]
+        );
+        assert_eq!(roster.name_by_player_id(2), "Bob");
+    }
+
+    #[test]
+    fn user_ids_come_from_the_lobby_without_player_setup() {
+        let (players, mut slots) = lobby();
+        // The slot ids do not follow the user ids once someone left the lobby before the game.
+        slots[2].user_id = Some(3);
+        let roster = PlayerRoster::resolve(&players, &slots, &[]);
+        let ids: Vec<(u8, Option<u32>, &str)> = roster
+            .iter()
+            .map(|entry| (entry.player_id, entry.user_id, entry.name.as_str()))
+            .collect();
+        assert_eq!(ids, vec![(1, Some(0), "Alice"), (2, Some(3), "Bob")]);
+        assert_eq!(roster.name_by_user_id(1), "Caster");
+    }
+}
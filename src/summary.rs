@@ -0,0 +1,269 @@
+//! The cooper replay summary, a versioned JSON document with everything cooper extracts from a
+//! replay. Summaries can be loaded back with `ProcessedReplay::from_summary` so the original
+//! SC2Replay file is not needed to display them again.
+//!
+//! Schema version 1, frames are game loops and player ids are the tracker events player ids:
+//!
+//! ```text
+//! {
+//!   "schema_version": 1,
+//!   "metadata": { "name", "title", "map_file_name", "description", "is_blizzard_map",
+//!                 "game_speed", "date": { "unix_seconds", "local_offset_seconds" },
+//!                 "last_frame", "duration_seconds", "content_hash", "random_seed",
+//!                 "map_size": [width, height] },
+//!   "roster": [ { "player_id", "user_id", "slot_id", "is_ai", "name", "race", "result",
+//!                 "team_id", "color": { "r", "g", "b", "a" },
+//!                 "toon": { "region", "realm", "id" } } ],
+//!   "observers": [ { "user_id", "name" } ],
+//!   "time_series": { "game_snapshots": [GameSnapshot], "apm": [PlayerApm] },
+//!   "events": { "messages": [ChatMessage], "units": [UnitLifecycle],
+//!               "upgrades": [UpgradeCompleted], "build_order": [BuildOrderEntry] }
+//! }
+//! ```
+//!
+//! Any change to the serialised types, here or in the types they contain, must bump
+//! `SUMMARY_SCHEMA_VERSION`. Optional fields added with `#[serde(default)]` are the exception,
+//! older summaries remain valid.
+
+use crate::{
+    BuildOrderEntry, ChatMessage, GameSnapshot, GameSpeed, PlayerApm, ProcessedReplay, ReplayDate,
+    ReplayError, ReplayPlayer, RosterObserver, UnitLifecycle, UpgradeCompleted,
+};
+use serde::{Deserialize, Serialize};
+
+/// The version of the summary schema written by this version of cooper.
+pub const SUMMARY_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SummaryMetadata {
+    /// The file name of the replay.
+    pub name: String,
+    pub title: String,
+    pub map_file_name: String,
+    pub description: String,
+    pub is_blizzard_map: bool,
+    pub game_speed: GameSpeed,
+    pub date: ReplayDate,
+    pub last_frame: u32,
+    /// Derived from `last_frame` and `game_speed`, for convenience of the readers.
+    pub duration_seconds: u32,
+    /// The hash of the SC2Replay file the summary was made from, see `ReplayIdentity`.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// The init data random seed, see `ReplayIdentity`.
+    #[serde(default)]
+    pub random_seed: Option<u32>,
+    /// The width and height of the map, in cells.
+    #[serde(default)]
+    pub map_size: Option<(u32, u32)>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SummaryPlayer {
+    /// The tracker events player id.
+    pub player_id: u8,
+    /// The game and message events user id, AI players do not have one.
+    pub user_id: Option<u32>,
+    pub slot_id: Option<u8>,
+    pub is_ai: bool,
+    #[serde(flatten)]
+    pub details: ReplayPlayer,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SummaryTimeSeries {
+    pub game_snapshots: Vec<GameSnapshot>,
+    pub apm: Vec<PlayerApm>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SummaryEvents {
+    pub messages: Vec<ChatMessage>,
+    pub units: Vec<UnitLifecycle>,
+    pub upgrades: Vec<UpgradeCompleted>,
+    pub build_order: Vec<BuildOrderEntry>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReplaySummary {
+    pub schema_version: u32,
+    pub metadata: SummaryMetadata,
+    pub roster: Vec<SummaryPlayer>,
+    /// Missing from the summaries written before the observers were named.
+    #[serde(default)]
+    pub observers: Vec<RosterObserver>,
+    pub time_series: SummaryTimeSeries,
+    pub events: SummaryEvents,
+}
+
+/// Only the version is read first, so that summaries from other versions are reported as such
+/// instead of as missing or unknown fields.
+#[derive(Deserialize)]
+struct SchemaVersion {
+    schema_version: u32,
+}
+
+/// Summaries are told apart from SC2Replay files by their extension.
+pub fn is_summary_file_name(name: &str) -> bool {
+    name.to_lowercase().ends_with(".json")
+}
+
+impl ReplaySummary {
+    pub fn new(replay: &ProcessedReplay) -> Self {
+        let roster = replay
+            .roster
+            .iter()
+            .map(|entry| SummaryPlayer {
+                player_id: entry.player_id,
+                user_id: entry.user_id,
+                slot_id: entry.slot_id,
+                is_ai: entry.is_ai,
+                details: replay.details.player_list[entry.details_idx].clone(),
+            })
+            .collect();
+        Self {
+            schema_version: SUMMARY_SCHEMA_VERSION,
+            metadata: SummaryMetadata {
+                name: replay.name.clone(),
+                title: replay.details.title.clone(),
+                map_file_name: replay.details.map_file_name.clone(),
+                description: replay.details.description.clone(),
+                is_blizzard_map: replay.details.is_blizzard_map,
+                game_speed: replay.game_speed,
+                date: replay.date,
+                last_frame: replay.last_frame,
+                duration_seconds: replay.duration_seconds(),
+                content_hash: replay.identity.content_hash.clone(),
+                random_seed: replay.identity.random_seed,
+                map_size: replay.map_size,
+            },
+            roster,
+            observers: replay.roster.observers().cloned().collect(),
+            time_series: SummaryTimeSeries {
+                game_snapshots: replay.game_snapshots.clone(),
+                apm: replay.apm.clone(),
+            },
+            events: SummaryEvents {
+                messages: replay.messages.clone(),
+                units: replay.units.iter().cloned().collect(),
+                upgrades: replay.upgrades.clone(),
+                build_order: replay.build_order.clone(),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, ReplayError> {
+        serde_json::to_string_pretty(self).map_err(|err| ReplayError::Summary(err.to_string()))
+    }
+
+    pub fn from_json(data: &[u8]) -> Result<Self, ReplayError> {
+        let version: SchemaVersion =
+            serde_json::from_slice(data).map_err(|err| ReplayError::Summary(err.to_string()))?;
+        if version.schema_version != SUMMARY_SCHEMA_VERSION {
+            return Err(ReplayError::UnsupportedSummaryVersion(
+                version.schema_version,
+            ));
+        }
+        serde_json::from_slice(data).map_err(|err| ReplayError::Summary(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameResult, PlayerColor, Toon};
+
+    fn summary() -> ReplaySummary {
+        let game_snapshot = serde_json::json!({
+            "frame": 160,
+            "player_id": 1,
+            "minerals": 50,
+            "vespene": 0,
+            "mineral_income": 300,
+            "vespene_income": 0,
+            "supply_available": 15,
+            "supply_used": 13,
+            "supply_workers": 13,
+            "active_force_minerals": 0,
+            "active_force_vespene": 0,
+        });
+        ReplaySummary {
+            schema_version: SUMMARY_SCHEMA_VERSION,
+            metadata: SummaryMetadata {
+                name: "game.SC2Replay".to_string(),
+                title: "Altitude LE".to_string(),
+                map_file_name: String::new(),
+                description: String::new(),
+                is_blizzard_map: true,
+                game_speed: GameSpeed::Faster,
+                date: ReplayDate {
+                    unix_seconds: 1_672_531_200,
+                    local_offset_seconds: 3600,
+                },
+                last_frame: 22400,
+                duration_seconds: 1000,
+                content_hash: Some("0123456789abcdef".to_string()),
+                random_seed: Some(42),
+                map_size: Some((176, 160)),
+            },
+            roster: vec![SummaryPlayer {
+                player_id: 1,
+                user_id: Some(0),
+                slot_id: Some(0),
+                is_ai: false,
+                details: ReplayPlayer {
+                    name: "player".to_string(),
+                    race: "Zerg".to_string(),
+                    result: GameResult::Win,
+                    team_id: 0,
+                    color: PlayerColor {
+                        r: 180,
+                        g: 20,
+                        b: 30,
+                        a: 255,
+                    },
+                    toon: Toon {
+                        region: 2,
+                        realm: 1,
+                        id: 123,
+                    },
+                },
+            }],
+            observers: vec![RosterObserver {
+                user_id: 1,
+                name: "Caster".to_string(),
+            }],
+            time_series: SummaryTimeSeries {
+                game_snapshots: vec![serde_json::from_value(game_snapshot).unwrap()],
+                apm: vec![],
+            },
+            events: SummaryEvents {
+                messages: vec![],
+                units: vec![],
+                upgrades: vec![],
+                build_order: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn summary_round_trip() {
+        let summary = summary();
+        assert_eq!(summary.time_series.game_snapshots[0].player_id, 1);
+        let replay = ProcessedReplay::from_summary(summary.clone());
+        let json = ReplaySummary::new(&replay).to_json().unwrap();
+        assert_eq!(ReplaySummary::from_json(json.as_bytes()).unwrap(), summary);
+    }
+
+    #[test]
+    fn newer_summaries_are_rejected() {
+        let mut summary = summary();
+        summary.schema_version = SUMMARY_SCHEMA_VERSION + 1;
+        assert_eq!(
+            ReplaySummary::from_json(summary.to_json().unwrap().as_bytes()),
+            Err(ReplayError::UnsupportedSummaryVersion(
+                SUMMARY_SCHEMA_VERSION + 1
+            ))
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Conversion of game loops (the `frame` of the events) into in-game time.
+
+use serde::{Deserialize, Serialize};
+
+/// The game speed the replay was played at. Ladder games are played at Faster.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum GameSpeed {
+    Slower,
+    Slow,
+    Normal,
+    Fast,
+    #[default]
+    Faster,
+}
+
+impl GameSpeed {
+    /// The game runs at 16 loops per second on Normal speed, the other speeds are multipliers
+    /// of it, i.e. Faster is 1.4 times Normal, or 22.4 loops per second.
+    pub fn loops_per_second(&self) -> f32 {
+        match self {
+            GameSpeed::Slower => 9.6,
+            GameSpeed::Slow => 12.8,
+            GameSpeed::Normal => 16.0,
+            GameSpeed::Fast => 19.2,
+            GameSpeed::Faster => 22.4,
+        }
+    }
+
+    /// Converts game loops to in-game seconds.
+    pub fn loops_to_seconds(&self, game_loop: u32) -> f32 {
+        game_loop as f32 / self.loops_per_second()
+    }
+
+    /// Converts in-game seconds to game loops.
+    pub fn seconds_to_loops(&self, seconds: f32) -> u32 {
+        (seconds * self.loops_per_second()).round() as u32
+    }
+
+    /// Formats game loops as the in-game clock, `mm:ss`, or `h:mm:ss` for games longer than an
+    /// hour.
+    pub fn format_loops(&self, game_loop: u32) -> String {
+        format_seconds(self.loops_to_seconds(game_loop) as u32)
+    }
+}
+
+impl From<&s2protocol::details::GameSpeed> for GameSpeed {
+    fn from(game_speed: &s2protocol::details::GameSpeed) -> Self {
+        match game_speed {
+            s2protocol::details::GameSpeed::ESlower => GameSpeed::Slower,
+            s2protocol::details::GameSpeed::ESlow => GameSpeed::Slow,
+            s2protocol::details::GameSpeed::ENormal => GameSpeed::Normal,
+            s2protocol::details::GameSpeed::EFast => GameSpeed::Fast,
+            s2protocol::details::GameSpeed::EFaster => GameSpeed::Faster,
+        }
+    }
+}
+
+/// Formats seconds as `mm:ss`, or `h:mm:ss` for an hour or more.
+pub fn format_seconds(seconds: u32) -> String {
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds % 3600) / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Seconds between the Windows FILETIME epoch (1601-01-01) and the unix epoch (1970-01-01).
+const FILETIME_UNIX_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// FILETIME values are in 100 nanosecond ticks.
+const FILETIME_TICKS_PER_SECOND: i64 = 10_000_000;
+
+/// A calendar date and time of day, without a time zone.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct CivilDateTime {
+    pub year: i64,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl CivilDateTime {
+    /// Converts seconds since the unix epoch to the proleptic gregorian calendar, this is Howard
+    /// Hinnant's `civil_from_days` algorithm.
+    pub fn from_unix_seconds(unix_seconds: i64) -> Self {
+        let days = unix_seconds.div_euclid(86_400);
+        let seconds_of_day = unix_seconds.rem_euclid(86_400);
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds_of_day / 3600) as u8,
+            minute: ((seconds_of_day % 3600) / 60) as u8,
+            second: (seconds_of_day % 60) as u8,
+        }
+    }
+}
+
+impl std::fmt::Display for CivilDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// The date a replay was played, decoded from the `Details::time_utc` and
+/// `Details::time_local_offset` Windows FILETIME values.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayDate {
+    /// Seconds since the unix epoch, in UTC. Useful for sorting a replay library.
+    pub unix_seconds: i64,
+    /// The offset of the local time of the player that saved the replay to UTC, in seconds.
+    pub local_offset_seconds: i64,
+}
+
+impl ReplayDate {
+    pub fn from_filetime(time_utc: i64, time_local_offset: i64) -> Self {
+        Self {
+            unix_seconds: time_utc / FILETIME_TICKS_PER_SECOND - FILETIME_UNIX_EPOCH_OFFSET,
+            local_offset_seconds: time_local_offset / FILETIME_TICKS_PER_SECOND,
+        }
+    }
+
+    pub fn utc(&self) -> CivilDateTime {
+        CivilDateTime::from_unix_seconds(self.unix_seconds)
+    }
+
+    pub fn local(&self) -> CivilDateTime {
+        CivilDateTime::from_unix_seconds(self.unix_seconds + self.local_offset_seconds)
+    }
+
+    /// Formats the UTC offset as `+hh:mm`.
+    pub fn format_local_offset(&self) -> String {
+        let sign = if self.local_offset_seconds < 0 {
+            '-'
+        } else {
+            '+'
+        };
+        let offset_minutes = self.local_offset_seconds.abs() / 60;
+        format!(
+            "{}{:02}:{:02}",
+            sign,
+            offset_minutes / 60,
+            offset_minutes % 60
+        )
+    }
+
+    pub fn format_utc(&self) -> String {
+        format!("{} UTC", self.utc())
+    }
+
+    pub fn format_local(&self) -> String {
+        format!("{} {}", self.local(), self.format_local_offset())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filetime_epoch() {
+        let date = ReplayDate::from_filetime(0, 0);
+        assert_eq!(date.unix_seconds, -FILETIME_UNIX_EPOCH_OFFSET);
+        assert_eq!(date.format_utc(), "1601-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn unix_epoch() {
+        let date = ReplayDate::from_filetime(116_444_736_000_000_000, 0);
+        assert_eq!(date.unix_seconds, 0);
+        assert_eq!(date.format_utc(), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn leap_day() {
+        let date = ReplayDate::from_filetime(133_536_836_960_000_000, 0);
+        assert_eq!(date.unix_seconds, 1_709_210_096);
+        assert_eq!(date.format_utc(), "2024-02-29 12:34:56 UTC");
+        assert_eq!(
+            CivilDateTime::from_unix_seconds(1_709_251_200).to_string(),
+            "2024-03-01 00:00:00"
+        );
+    }
+
+    #[test]
+    fn negative_local_offset() {
+        // Newfoundland, UTC-03:30.
+        let date = ReplayDate::from_filetime(116_444_736_000_000_000, -126_000_000_000);
+        assert_eq!(date.local_offset_seconds, -12_600);
+        assert_eq!(date.format_local_offset(), "-03:30");
+        assert_eq!(date.format_local(), "1969-12-31 20:30:00 -03:30");
+    }
+}
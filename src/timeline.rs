@@ -0,0 +1,177 @@
+use crate::canvas::{container_width, resize_canvas, PlotLayout, ResizeWatcher};
+use crate::GameSpeed;
+use gloo_console::log;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters_canvas::CanvasBackend;
+use web_sys::HtmlCanvasElement;
+use yew::prelude::*;
+
+pub enum TimelineMsg {
+    Redraw,
+}
+
+/// An event shown on a timeline lane, i.e. an upgrade finishing.
+#[derive(PartialEq)]
+pub struct TimelineEvent {
+    pub x: u32,
+    pub label: String,
+}
+
+/// A row of the timeline, usually one per player.
+#[derive(PartialEq)]
+pub struct TimelineLane {
+    pub events: Vec<TimelineEvent>,
+    pub color: RGBColor,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TimelineProperties {
+    pub lanes: Vec<TimelineLane>,
+    /// The end of the x axis, should match the `Plot`s the timeline is shown with so that they
+    /// are aligned.
+    pub max_x: u32,
+    #[prop_or_default]
+    pub game_speed: GameSpeed,
+    /// The hovered frame of the `Plot`s, drawn as a vertical line.
+    #[prop_or_default]
+    pub cursor: Option<u32>,
+    /// The zoomed in range of frames of the `Plot`s.
+    #[prop_or_default]
+    pub x_range: Option<(u32, u32)>,
+    /// Should match the layout of the `Plot`s so that they have the same width.
+    #[prop_or_default]
+    pub layout: PlotLayout,
+}
+
+/// Draws labelled events on a time axis, the margins match those of `Plot` so that the events
+/// line up with the series drawn above or below.
+pub struct Timeline {
+    canvas: NodeRef,
+    container: NodeRef,
+    resize_watcher: ResizeWatcher,
+}
+
+impl Component for Timeline {
+    type Message = TimelineMsg;
+    type Properties = TimelineProperties;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(TimelineMsg::Redraw);
+        Timeline {
+            canvas: NodeRef::default(),
+            container: NodeRef::default(),
+            resize_watcher: ResizeWatcher::new(ctx.link().callback(|_| TimelineMsg::Redraw)),
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props() != old_props {
+            ctx.link().send_message(TimelineMsg::Redraw);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            TimelineMsg::Redraw => {
+                let element: HtmlCanvasElement = match self.canvas.cast() {
+                    Some(element) => element,
+                    None => return false,
+                };
+
+                let (css_width, _) = ctx
+                    .props()
+                    .layout
+                    .plot_size(container_width(self.container.cast()));
+                let css_height = 40 + 40 * ctx.props().lanes.len().max(1) as u32;
+                let scale = resize_canvas(&element, css_width, css_height);
+                self.resize_watcher.watch_device_pixel_ratio();
+
+                let backend = match CanvasBackend::with_canvas_object(element) {
+                    Some(backend) => backend,
+                    None => return false,
+                };
+                let drawing_area = backend.into_drawing_area();
+                if let Err(err) = draw_timeline(&drawing_area, ctx.props(), scale) {
+                    log!("Unable to draw the timeline", err.to_string());
+                }
+                false
+            }
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        html!(
+            <div ref={self.container.clone()}>
+                <canvas ref = {self.canvas.clone()}/>
+            </div>
+        )
+    }
+}
+
+/// Draws the lanes and the cursor, `scale` is the number of backend pixels per CSS pixel.
+fn draw_timeline<DB: DrawingBackend>(
+    drawing_area: &DrawingArea<DB, Shift>,
+    props: &TimelineProperties,
+    scale: f64,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let px = |size: f64| (size * scale).round() as i32;
+    drawing_area.fill(&RGBColor(200, 200, 200))?;
+
+    let lanes = &props.lanes;
+    let (min_x, max_x) = props.x_range.unwrap_or((0, props.max_x));
+    let max_x = max_x.max(min_x + 1);
+    let max_y = lanes.len().max(1) as f32 - 0.5;
+
+    let mut chart = ChartBuilder::on(drawing_area)
+        .margin(px(5.) as u32)
+        .x_label_area_size(px(40.) as u32)
+        .y_label_area_size(px(60.) as u32)
+        .build_cartesian_2d(min_x..max_x, -0.5f32..max_y)?;
+
+    let game_speed = props.game_speed;
+    chart
+        .configure_mesh()
+        .x_label_formatter(&|x| game_speed.format_loops(*x))
+        .disable_y_mesh()
+        .disable_y_axis()
+        .label_style(("sans-serif", 12. * scale))
+        .axis_desc_style(("sans-serif", 15. * scale))
+        .draw()?;
+
+    for (lane_idx, lane) in lanes.iter().enumerate() {
+        let y = lane_idx as f32;
+        let color = lane.color;
+        chart.draw_series(
+            lane.events
+                .iter()
+                .filter(|event| event.x >= min_x && event.x <= max_x)
+                .enumerate()
+                .map(|(idx, event)| {
+                    // Alternate the labels above and below the marker so that upgrades
+                    // finishing at the same time are still readable.
+                    let label_offset = if idx % 2 == 0 { px(-14.) } else { px(4.) };
+                    EmptyElement::at((event.x, y))
+                        + Circle::new((0, 0), px(3.), color.filled())
+                        + Text::new(
+                            format!("{} {}", game_speed.format_loops(event.x), event.label),
+                            (px(5.), label_offset),
+                            ("sans-serif", 10. * scale).into_font(),
+                        )
+                }),
+        )?;
+    }
+
+    if let Some(cursor) = props.cursor {
+        if cursor >= min_x && cursor <= max_x {
+            chart.draw_series(std::iter::once(PathElement::new(
+                vec![(cursor, -0.5), (cursor, max_y)],
+                BLACK.mix(0.5),
+            )))?;
+        }
+    }
+    Ok(())
+}
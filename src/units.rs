@@ -0,0 +1,620 @@
+//! Unit lifecycle tracking from the UnitBorn/UnitInit/UnitDone/UnitDied/UnitTypeChange tracker
+//! events, along with the unit positions from those events and the periodic UnitPositions.
+
+use s2protocol::tracker_events::{ReplayTrackerEvent, TrackerEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Units that are not produced by the player, are produced as a side effect of something else
+/// or die on a timer. They are left out of the build order and of the losses.
+pub(crate) const IGNORED_UNIT_TYPES: [&str; 17] = [
+    "Larva",
+    "Egg",
+    "BanelingCocoon",
+    "RavagerCocoon",
+    "LurkerMPEgg",
+    "BroodLordCocoon",
+    "OverlordCocoon",
+    "TransportOverlordCocoon",
+    "Broodling",
+    "Interceptor",
+    "LocustMP",
+    "LocustMPFlying",
+    "AdeptPhaseShift",
+    "MULE",
+    "AutoTurret",
+    "CreepTumor",
+    "CreepTumorBurrowed",
+];
+
+/// The unit types of the structures, in every mode they can be in (flying, lowered, uprooted).
+const STRUCTURE_TYPES: [&str; 71] = [
+    "Nexus",
+    "Pylon",
+    "Assimilator",
+    "AssimilatorRich",
+    "Gateway",
+    "WarpGate",
+    "Forge",
+    "CyberneticsCore",
+    "PhotonCannon",
+    "ShieldBattery",
+    "TwilightCouncil",
+    "Stargate",
+    "RoboticsFacility",
+    "RoboticsBay",
+    "TemplarArchive",
+    "DarkShrine",
+    "FleetBeacon",
+    "CommandCenter",
+    "CommandCenterFlying",
+    "OrbitalCommand",
+    "OrbitalCommandFlying",
+    "PlanetaryFortress",
+    "SupplyDepot",
+    "SupplyDepotLowered",
+    "Refinery",
+    "RefineryRich",
+    "Barracks",
+    "BarracksFlying",
+    "BarracksReactor",
+    "BarracksTechLab",
+    "Factory",
+    "FactoryFlying",
+    "FactoryReactor",
+    "FactoryTechLab",
+    "Starport",
+    "StarportFlying",
+    "StarportReactor",
+    "StarportTechLab",
+    "Reactor",
+    "TechLab",
+    "EngineeringBay",
+    "Armory",
+    "Bunker",
+    "MissileTurret",
+    "SensorTower",
+    "GhostAcademy",
+    "FusionCore",
+    "Hatchery",
+    "Lair",
+    "Hive",
+    "Extractor",
+    "ExtractorRich",
+    "SpawningPool",
+    "EvolutionChamber",
+    "RoachWarren",
+    "BanelingNest",
+    "SpineCrawler",
+    "SpineCrawlerUprooted",
+    "SporeCrawler",
+    "SporeCrawlerUprooted",
+    "HydraliskDen",
+    "LurkerDenMP",
+    "InfestationPit",
+    "Spire",
+    "GreaterSpire",
+    "NydusNetwork",
+    "NydusCanal",
+    "UltraliskCavern",
+    "CreepTumor",
+    "CreepTumorBurrowed",
+    "CreepTumorQueen",
+];
+
+/// Units are identified by their index and a recycle counter, indexes are reused by the game
+/// once a unit dies.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize)]
+pub struct UnitTag {
+    pub index: u32,
+    pub recycle: u32,
+}
+
+impl UnitTag {
+    pub fn new(index: u32, recycle: u32) -> Self {
+        Self { index, recycle }
+    }
+}
+
+/// Where a unit was at a frame, in map cells.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub struct UnitPosition {
+    pub frame: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// A change of unit type, i.e. a Hatchery morphing into a Lair or a SiegeTank sieging.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UnitMorph {
+    pub frame: u32,
+    pub unit_type_name: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UnitLifecycle {
+    pub tag: UnitTag,
+    /// The unit type the unit was born or initialized as, see `morphs` for later changes.
+    pub unit_type_name: String,
+    /// The player that controls the unit, updated on UnitOwnerChange.
+    pub owner: u8,
+    /// The frame of the UnitBorn or UnitInit event.
+    pub creation_frame: u32,
+    /// For units that are born this is the same as the `creation_frame`, for structures and
+    /// units that are initialized this is the frame of their UnitDone event.
+    pub completion_frame: Option<u32>,
+    pub death_frame: Option<u32>,
+    pub killer_player_id: Option<u8>,
+    pub killer_unit: Option<UnitTag>,
+    pub morphs: Vec<UnitMorph>,
+    /// Known positions, ordered by frame. The UnitPositions events are only emitted
+    /// periodically and only for units that moved or dealt damage, so the position in between
+    /// is the last known one.
+    #[serde(default)]
+    pub positions: Vec<UnitPosition>,
+}
+
+impl UnitLifecycle {
+    /// The unit type after the last morph.
+    pub fn current_type_name(&self) -> &str {
+        self.morphs
+            .last()
+            .map(|morph| morph.unit_type_name.as_str())
+            .unwrap_or(&self.unit_type_name)
+    }
+
+    /// The last known position of the unit at `frame`.
+    pub fn position_at(&self, frame: u32) -> Option<UnitPosition> {
+        self.positions
+            .iter()
+            .take_while(|position| position.frame <= frame)
+            .last()
+            .copied()
+    }
+
+    /// Whether the unit is a structure, by its current type. Units that are warped in are
+    /// initialized and completed later like structures, so the events cannot tell them apart.
+    pub fn is_structure(&self) -> bool {
+        is_structure_type(self.current_type_name())
+    }
+
+    /// Whether the unit existed, either in progress or completed, at `frame`.
+    pub fn is_alive_at(&self, frame: u32) -> bool {
+        self.creation_frame <= frame && self.death_frame.map_or(true, |death| death > frame)
+    }
+}
+
+pub fn is_structure_type(unit_type_name: &str) -> bool {
+    STRUCTURE_TYPES.contains(&unit_type_name)
+}
+
+/// The units of a replay, keyed by their tag.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct UnitRegistry {
+    units: BTreeMap<UnitTag, UnitLifecycle>,
+}
+
+impl UnitRegistry {
+    pub fn from_tracker_events(tracker_events: &[TrackerEvent]) -> Self {
+        let mut registry = Self::default();
+        // UnitPositions only contain the unit index, the recycle is that of the live unit.
+        let mut live_by_index: HashMap<u32, UnitTag> = HashMap::new();
+        let mut frame = 0;
+        for event in tracker_events {
+            frame += event.delta;
+            match &event.event {
+                ReplayTrackerEvent::UnitBorn(unit_born) => {
+                    let tag = UnitTag::new(unit_born.unit_tag_index, unit_born.unit_tag_recycle);
+                    registry.units.insert(
+                        tag,
+                        UnitLifecycle {
+                            tag,
+                            unit_type_name: unit_born.unit_type_name.clone(),
+                            owner: unit_born.control_player_id,
+                            creation_frame: frame,
+                            completion_frame: Some(frame),
+                            death_frame: None,
+                            killer_player_id: None,
+                            killer_unit: None,
+                            morphs: vec![],
+                            positions: vec![UnitPosition {
+                                frame,
+                                x: u32::from(unit_born.x),
+                                y: u32::from(unit_born.y),
+                            }],
+                        },
+                    );
+                    live_by_index.insert(tag.index, tag);
+                }
+                ReplayTrackerEvent::UnitInit(unit_init) => {
+                    let tag = UnitTag::new(unit_init.unit_tag_index, unit_init.unit_tag_recycle);
+                    registry.units.insert(
+                        tag,
+                        UnitLifecycle {
+                            tag,
+                            unit_type_name: unit_init.unit_type_name.clone(),
+                            owner: unit_init.control_player_id,
+                            creation_frame: frame,
+                            completion_frame: None,
+                            death_frame: None,
+                            killer_player_id: None,
+                            killer_unit: None,
+                            morphs: vec![],
+                            positions: vec![UnitPosition {
+                                frame,
+                                x: u32::from(unit_init.x),
+                                y: u32::from(unit_init.y),
+                            }],
+                        },
+                    );
+                    live_by_index.insert(tag.index, tag);
+                }
+                ReplayTrackerEvent::UnitDone(unit_done) => {
+                    let tag = UnitTag::new(unit_done.unit_tag_index, unit_done.unit_tag_recycle);
+                    if let Some(unit) = registry.units.get_mut(&tag) {
+                        unit.completion_frame = Some(frame);
+                    }
+                }
+                ReplayTrackerEvent::UnitDied(unit_died) => {
+                    let tag = UnitTag::new(unit_died.unit_tag_index, unit_died.unit_tag_recycle);
+                    if let Some(unit) = registry.units.get_mut(&tag) {
+                        unit.death_frame = Some(frame);
+                        unit.killer_player_id = unit_died.killer_player_id;
+                        unit.killer_unit = unit_died
+                            .killer_unit_tag_index
+                            .zip(unit_died.killer_unit_tag_recycle)
+                            .map(|(index, recycle)| UnitTag::new(index, recycle));
+                        unit.positions.push(UnitPosition {
+                            frame,
+                            x: u32::from(unit_died.x),
+                            y: u32::from(unit_died.y),
+                        });
+                    }
+                    live_by_index.remove(&tag.index);
+                }
+                ReplayTrackerEvent::UnitTypeChange(type_change) => {
+                    let tag =
+                        UnitTag::new(type_change.unit_tag_index, type_change.unit_tag_recycle);
+                    if let Some(unit) = registry.units.get_mut(&tag) {
+                        unit.morphs.push(UnitMorph {
+                            frame,
+                            unit_type_name: type_change.unit_type_name.clone(),
+                        });
+                    }
+                }
+                ReplayTrackerEvent::UnitPositions(unit_positions) => {
+                    // The items are triplets of the unit index delta from the previous item, x
+                    // and y. The coordinates are stored at a quarter of the cell coordinates of
+                    // the other unit events.
+                    let mut index = unit_positions.first_unit_index;
+                    for item in unit_positions.items.chunks_exact(3) {
+                        index = index.wrapping_add(item[0] as u32);
+                        let unit = live_by_index
+                            .get(&index)
+                            .and_then(|tag| registry.units.get_mut(tag));
+                        if let Some(unit) = unit {
+                            unit.positions.push(UnitPosition {
+                                frame,
+                                x: item[1].max(0) as u32 * 4,
+                                y: item[2].max(0) as u32 * 4,
+                            });
+                        }
+                    }
+                }
+                ReplayTrackerEvent::UnitOwnerChange(owner_change) => {
+                    let tag =
+                        UnitTag::new(owner_change.unit_tag_index, owner_change.unit_tag_recycle);
+                    if let Some(unit) = registry.units.get_mut(&tag) {
+                        unit.owner = owner_change.control_player_id;
+                    }
+                }
+                _ => {}
+            }
+        }
+        registry
+    }
+
+    /// Rebuilds a registry from previously extracted units, i.e. from a `ReplaySummary`.
+    pub fn from_units(units: impl IntoIterator<Item = UnitLifecycle>) -> Self {
+        Self {
+            units: units.into_iter().map(|unit| (unit.tag, unit)).collect(),
+        }
+    }
+
+    pub fn get(&self, tag: &UnitTag) -> Option<&UnitLifecycle> {
+        self.units.get(tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// All the units, ordered by tag.
+    pub fn iter(&self) -> impl Iterator<Item = &UnitLifecycle> {
+        self.units.values()
+    }
+
+    /// The extent of the known unit positions, in map cells. An approximation of the map size
+    /// for when `ProcessedReplay::map_size` is None.
+    pub fn map_extent(&self) -> (u32, u32) {
+        self.iter()
+            .flat_map(|unit| unit.positions.iter())
+            .fold((0, 0), |(width, height), position| {
+                (width.max(position.x + 1), height.max(position.y + 1))
+            })
+    }
+
+    pub fn owned_by(&self, player_id: u8) -> impl Iterator<Item = &UnitLifecycle> {
+        self.iter().filter(move |unit| unit.owner == player_id)
+    }
+
+    /// Units that were created, in progress or completed, by the end of `frame` and had not
+    /// died yet.
+    pub fn alive_at(&self, frame: u32) -> impl Iterator<Item = &UnitLifecycle> {
+        self.iter().filter(move |unit| unit.is_alive_at(frame))
+    }
+
+    /// Units that were initialized (i.e. structures being warped in/built) but never completed.
+    pub fn never_completed(&self) -> impl Iterator<Item = &UnitLifecycle> {
+        self.iter().filter(|unit| unit.completion_frame.is_none())
+    }
+
+    /// Units that died, ordered by their death frame.
+    pub fn dead(&self) -> Vec<&UnitLifecycle> {
+        let mut dead: Vec<&UnitLifecycle> = self
+            .iter()
+            .filter(|unit| unit.death_frame.is_some())
+            .collect();
+        dead.sort_by_key(|unit| unit.death_frame);
+        dead
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s2protocol::tracker_events::{
+        UnitBornEvent, UnitDiedEvent, UnitDoneEvent, UnitInitEvent, UnitOwnerChangeEvent,
+        UnitPositionsEvent, UnitTypeChangeEvent,
+    };
+
+    fn born(
+        delta: u32,
+        index: u32,
+        recycle: u32,
+        unit_type_name: &str,
+        player: u8,
+    ) -> TrackerEvent {
+        TrackerEvent {
+            delta,
+            event: ReplayTrackerEvent::UnitBorn(UnitBornEvent {
+                unit_tag_index: index,
+                unit_tag_recycle: recycle,
+                unit_type_name: unit_type_name.to_string(),
+                control_player_id: player,
+                upkeep_player_id: player,
+                x: 30,
+                y: 40,
+                creator_unit_tag_index: None,
+                creator_unit_tag_recycle: None,
+                creator_ability_name: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn born_units_are_complete_and_initialized_units_complete_when_done() {
+        let tracker_events = vec![
+            born(10, 1, 1, "Probe", 1),
+            TrackerEvent {
+                delta: 10,
+                event: ReplayTrackerEvent::UnitInit(UnitInitEvent {
+                    unit_tag_index: 2,
+                    unit_tag_recycle: 1,
+                    unit_type_name: "Pylon".to_string(),
+                    control_player_id: 1,
+                    upkeep_player_id: 1,
+                    x: 50,
+                    y: 60,
+                }),
+            },
+            TrackerEvent {
+                delta: 10,
+                event: ReplayTrackerEvent::UnitInit(UnitInitEvent {
+                    unit_tag_index: 3,
+                    unit_tag_recycle: 1,
+                    unit_type_name: "Gateway".to_string(),
+                    control_player_id: 1,
+                    upkeep_player_id: 1,
+                    x: 54,
+                    y: 60,
+                }),
+            },
+            TrackerEvent {
+                delta: 390,
+                event: ReplayTrackerEvent::UnitDone(UnitDoneEvent {
+                    unit_tag_index: 2,
+                    unit_tag_recycle: 1,
+                }),
+            },
+        ];
+        let registry = UnitRegistry::from_tracker_events(&tracker_events);
+        let probe = registry.get(&UnitTag::new(1, 1)).unwrap();
+        assert_eq!(
+            (probe.creation_frame, probe.completion_frame),
+            (10, Some(10))
+        );
+        let pylon = registry.get(&UnitTag::new(2, 1)).unwrap();
+        assert_eq!(
+            (pylon.creation_frame, pylon.completion_frame),
+            (20, Some(420))
+        );
+        assert_eq!(
+            pylon.positions,
+            vec![UnitPosition {
+                frame: 20,
+                x: 50,
+                y: 60,
+            }]
+        );
+        let never_completed: Vec<&str> = registry
+            .never_completed()
+            .map(|unit| unit.unit_type_name.as_str())
+            .collect();
+        assert_eq!(never_completed, vec!["Gateway"]);
+    }
+
+    #[test]
+    fn recycled_tag_indexes_are_distinct_units() {
+        let tracker_events = vec![
+            born(10, 5, 1, "Zergling", 1),
+            TrackerEvent {
+                delta: 40,
+                event: ReplayTrackerEvent::UnitDied(UnitDiedEvent {
+                    unit_tag_index: 5,
+                    unit_tag_recycle: 1,
+                    killer_player_id: None,
+                    x: 31,
+                    y: 41,
+                    killer_unit_tag_index: None,
+                    killer_unit_tag_recycle: None,
+                }),
+            },
+            born(10, 5, 2, "Marine", 2),
+        ];
+        let registry = UnitRegistry::from_tracker_events(&tracker_events);
+        assert_eq!(registry.len(), 2);
+        let zergling = registry.get(&UnitTag::new(5, 1)).unwrap();
+        assert_eq!(zergling.unit_type_name, "Zergling");
+        assert_eq!(zergling.death_frame, Some(50));
+        let marine = registry.get(&UnitTag::new(5, 2)).unwrap();
+        assert_eq!(marine.unit_type_name, "Marine");
+        assert_eq!((marine.creation_frame, marine.death_frame), (60, None));
+    }
+
+    #[test]
+    fn type_changes_are_morphs() {
+        let tracker_events = vec![
+            born(100, 3, 1, "Larva", 1),
+            TrackerEvent {
+                delta: 50,
+                event: ReplayTrackerEvent::UnitTypeChange(UnitTypeChangeEvent {
+                    unit_tag_index: 3,
+                    unit_tag_recycle: 1,
+                    unit_type_name: "Egg".to_string(),
+                }),
+            },
+            TrackerEvent {
+                delta: 272,
+                event: ReplayTrackerEvent::UnitTypeChange(UnitTypeChangeEvent {
+                    unit_tag_index: 3,
+                    unit_tag_recycle: 1,
+                    unit_type_name: "Drone".to_string(),
+                }),
+            },
+        ];
+        let registry = UnitRegistry::from_tracker_events(&tracker_events);
+        let unit = registry.get(&UnitTag::new(3, 1)).unwrap();
+        assert_eq!(unit.unit_type_name, "Larva");
+        assert_eq!(unit.current_type_name(), "Drone");
+        assert_eq!(
+            unit.morphs,
+            vec![
+                UnitMorph {
+                    frame: 150,
+                    unit_type_name: "Egg".to_string(),
+                },
+                UnitMorph {
+                    frame: 422,
+                    unit_type_name: "Drone".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn owner_changes_update_the_owner() {
+        let tracker_events = vec![
+            born(10, 4, 1, "SCV", 1),
+            TrackerEvent {
+                delta: 100,
+                event: ReplayTrackerEvent::UnitOwnerChange(UnitOwnerChangeEvent {
+                    unit_tag_index: 4,
+                    unit_tag_recycle: 1,
+                    control_player_id: 2,
+                    upkeep_player_id: 2,
+                }),
+            },
+        ];
+        let registry = UnitRegistry::from_tracker_events(&tracker_events);
+        assert_eq!(registry.get(&UnitTag::new(4, 1)).unwrap().owner, 2);
+        assert_eq!(registry.owned_by(1).count(), 0);
+    }
+
+    #[test]
+    fn deaths_record_the_killer() {
+        let tracker_events = vec![
+            born(10, 7, 1, "Marine", 1),
+            born(0, 8, 1, "Zealot", 2),
+            TrackerEvent {
+                delta: 200,
+                event: ReplayTrackerEvent::UnitDied(UnitDiedEvent {
+                    unit_tag_index: 7,
+                    unit_tag_recycle: 1,
+                    killer_player_id: Some(2),
+                    x: 35,
+                    y: 45,
+                    killer_unit_tag_index: Some(8),
+                    killer_unit_tag_recycle: Some(1),
+                }),
+            },
+        ];
+        let registry = UnitRegistry::from_tracker_events(&tracker_events);
+        let marine = registry.get(&UnitTag::new(7, 1)).unwrap();
+        assert_eq!(marine.death_frame, Some(210));
+        assert_eq!(marine.killer_player_id, Some(2));
+        assert_eq!(marine.killer_unit, Some(UnitTag::new(8, 1)));
+        assert_eq!(
+            marine.position_at(210),
+            Some(UnitPosition {
+                frame: 210,
+                x: 35,
+                y: 45,
+            })
+        );
+        let dead: Vec<UnitTag> = registry.dead().iter().map(|unit| unit.tag).collect();
+        assert_eq!(dead, vec![UnitTag::new(7, 1)]);
+    }
+
+    #[test]
+    fn unit_positions_are_decoded_by_index_delta() {
+        let tracker_events = vec![
+            born(10, 4, 1, "Marine", 1),
+            born(0, 6, 1, "Marine", 1),
+            born(0, 9, 2, "Marauder", 1),
+            TrackerEvent {
+                delta: 150,
+                event: ReplayTrackerEvent::UnitPositions(UnitPositionsEvent {
+                    first_unit_index: 4,
+                    // Index 4, 6 (4 + 2), 8 which is not alive and 9 (8 + 1).
+                    items: vec![0, 10, 11, 2, 12, 13, 2, 1, 1, 1, 20, 21],
+                }),
+            },
+        ];
+        let registry = UnitRegistry::from_tracker_events(&tracker_events);
+        let last_position = |index, recycle| {
+            registry
+                .get(&UnitTag::new(index, recycle))
+                .and_then(|unit| unit.position_at(160))
+                .map(|position| (position.frame, position.x, position.y))
+        };
+        assert_eq!(last_position(4, 1), Some((160, 40, 44)));
+        assert_eq!(last_position(6, 1), Some((160, 48, 52)));
+        assert_eq!(last_position(9, 2), Some((160, 80, 84)));
+        assert_eq!(registry.map_extent(), (81, 85));
+    }
+}
@@ -0,0 +1,100 @@
+//! Upgrade and research completion from the Upgrade tracker events.
+
+use s2protocol::tracker_events::{ReplayTrackerEvent, TrackerEvent};
+use serde::{Deserialize, Serialize};
+
+/// Upgrades given by the game, sprays and rewards are not researched by the player.
+const IGNORED_UPGRADE_PREFIXES: [&str; 3] = ["Spray", "Reward", "GameHeart"];
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UpgradeCompleted {
+    /// The frame the research finished.
+    pub frame: u32,
+    /// The tracker events player id.
+    pub player_id: u8,
+    pub name: String,
+    pub count: i32,
+}
+
+/// Returns the upgrades researched by the players, ordered by frame. Upgrades that exist at the
+/// start of the game, i.e. the sprays or the race specific defaults are skipped.
+pub fn extract_upgrades(tracker_events: &[TrackerEvent]) -> Vec<UpgradeCompleted> {
+    let mut frame = 0;
+    let mut upgrades = vec![];
+    for event in tracker_events {
+        frame += event.delta;
+        if let ReplayTrackerEvent::Upgrade(upgrade) = &event.event {
+            if frame == 0
+                || IGNORED_UPGRADE_PREFIXES
+                    .iter()
+                    .any(|prefix| upgrade.upgrade_type_name.starts_with(prefix))
+            {
+                continue;
+            }
+            upgrades.push(UpgradeCompleted {
+                frame,
+                player_id: upgrade.player_id,
+                name: upgrade.upgrade_type_name.clone(),
+                count: upgrade.count,
+            });
+        }
+    }
+    upgrades
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use s2protocol::tracker_events::UpgradeEvent;
+
+    fn upgrade(delta: u32, player_id: u8, upgrade_type_name: &str) -> TrackerEvent {
+        TrackerEvent {
+            delta,
+            event: ReplayTrackerEvent::Upgrade(UpgradeEvent {
+                player_id,
+                upgrade_type_name: upgrade_type_name.to_string(),
+                count: 1,
+            }),
+        }
+    }
+
+    #[test]
+    fn upgrades_are_framed_by_the_accumulated_deltas() {
+        let events = vec![
+            upgrade(0, 1, "ProtossGroundWeaponsLevel1"),
+            upgrade(1000, 1, "WarpGateResearch"),
+            upgrade(500, 2, "GlialReconstitution"),
+        ];
+        let upgrades = extract_upgrades(&events);
+        assert_eq!(
+            upgrades
+                .iter()
+                .map(|upgrade| (upgrade.frame, upgrade.player_id, upgrade.name.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (1000, 1, "WarpGateResearch"),
+                (1500, 2, "GlialReconstitution")
+            ]
+        );
+    }
+
+    #[test]
+    fn game_given_upgrades_are_skipped() {
+        let events = vec![
+            upgrade(10, 1, "SprayTerran"),
+            upgrade(0, 1, "RewardDanceViking"),
+            upgrade(0, 2, "GameHeartActive"),
+            upgrade(0, 2, "Stimpack"),
+        ];
+        let upgrades = extract_upgrades(&events);
+        assert_eq!(
+            upgrades,
+            [UpgradeCompleted {
+                frame: 10,
+                player_id: 2,
+                name: "Stimpack".to_string(),
+                count: 1,
+            }]
+        );
+    }
+}